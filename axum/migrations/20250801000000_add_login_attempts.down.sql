@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS login_attempts_ip_idx;
+DROP INDEX IF EXISTS login_attempts_username_idx;
+DROP TABLE IF EXISTS login_attempts;
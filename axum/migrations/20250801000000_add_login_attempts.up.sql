@@ -0,0 +1,15 @@
+-- Audit log of failed login attempts
+CREATE TABLE IF NOT EXISTS login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    username TEXT NOT NULL,
+    ip TEXT NOT NULL,
+    user_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS login_attempts_username_idx
+    ON login_attempts (username, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS login_attempts_ip_idx
+    ON login_attempts (ip, created_at DESC);
@@ -0,0 +1,12 @@
+-- Write-ahead journal for post updates absorbed by the write-back cache
+CREATE TABLE IF NOT EXISTS pending_writes (
+    seq BIGSERIAL PRIMARY KEY,
+    post_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    title TEXT,
+    content TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS pending_writes_post_id_idx
+    ON pending_writes (post_id, seq);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS post_edges_target_id_idx;
+DROP INDEX IF EXISTS post_edges_user_id_idx;
+DROP TABLE IF EXISTS post_edges;
+DROP INDEX IF EXISTS posts_embedding_idx;
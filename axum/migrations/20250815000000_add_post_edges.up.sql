@@ -0,0 +1,37 @@
+-- Dropping the embedding column (vector 384 -> 512) also dropped its HNSW index
+CREATE INDEX IF NOT EXISTS posts_embedding_idx
+    ON posts
+    USING hnsw (embedding vector_cosine_ops);
+
+-- Precomputed nearest neighbors per post (similarity = 1 - cosine distance / 2)
+CREATE TABLE IF NOT EXISTS post_edges (
+    source_id BIGINT NOT NULL,
+    target_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    similarity REAL NOT NULL,
+    PRIMARY KEY (source_id, target_id),
+    FOREIGN KEY (source_id) REFERENCES posts(id) ON DELETE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES posts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS post_edges_user_id_idx
+    ON post_edges (user_id);
+
+CREATE INDEX IF NOT EXISTS post_edges_target_id_idx
+    ON post_edges (target_id);
+
+-- Backfill for posts that already have embeddings
+INSERT INTO post_edges (source_id, target_id, user_id, similarity)
+SELECT p1.id, p2.id, p1.user_id, 1 - (p1.embedding <=> p2.embedding) / 2
+FROM posts p1
+JOIN LATERAL (
+    SELECT id, embedding
+    FROM posts p2
+    WHERE p2.user_id = p1.user_id
+    AND p2.id != p1.id
+    AND p2.embedding IS NOT NULL
+    ORDER BY p2.embedding <=> p1.embedding
+    LIMIT 5
+) p2 ON TRUE
+WHERE p1.embedding IS NOT NULL
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS post_clusters_user_id_idx;
+DROP TABLE IF EXISTS post_clusters;
+DROP TABLE IF EXISTS clusters;
@@ -0,0 +1,20 @@
+-- Topic clusters over post embeddings, recomputed per user in the background
+CREATE TABLE IF NOT EXISTS clusters (
+    user_id BIGINT NOT NULL,
+    cluster_id INT NOT NULL,
+    label TEXT NOT NULL,
+    terms TEXT[] NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, cluster_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS post_clusters (
+    post_id BIGINT PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    cluster_id INT NOT NULL,
+    FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS post_clusters_user_id_idx
+    ON post_clusters (user_id, cluster_id);
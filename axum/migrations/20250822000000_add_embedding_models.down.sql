@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS embedding_models_status_idx;
+DROP TABLE IF EXISTS embedding_models;
+ALTER TABLE posts
+    DROP COLUMN IF EXISTS embedding_next_model,
+    DROP COLUMN IF EXISTS embedding_next,
+    DROP COLUMN IF EXISTS embedding_dim,
+    DROP COLUMN IF EXISTS embedding_model;
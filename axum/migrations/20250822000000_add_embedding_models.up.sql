@@ -0,0 +1,32 @@
+-- Embedding model of each row, plus a second column filled in the background
+-- while switching models (see src/posts/reembed.rs)
+ALTER TABLE posts
+    ADD COLUMN IF NOT EXISTS embedding_model TEXT,
+    ADD COLUMN IF NOT EXISTS embedding_dim INT,
+    ADD COLUMN IF NOT EXISTS embedding_next vector,
+    ADD COLUMN IF NOT EXISTS embedding_next_model TEXT;
+
+-- Existing embeddings come from the model the embedding API shipped with
+UPDATE posts
+SET embedding_model = 'distiluse-base-multilingual-cased-v2',
+    embedding_dim = vector_dims(embedding)
+WHERE embedding IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS embedding_models (
+    model TEXT PRIMARY KEY,
+    dimension INT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('active', 'migrating', 'retired', 'cancelled')),
+    failed BIGINT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+-- At most one model in use and one being migrated to
+CREATE UNIQUE INDEX IF NOT EXISTS embedding_models_status_idx
+    ON embedding_models (status)
+    WHERE status IN ('active', 'migrating');
+
+INSERT INTO embedding_models (model, dimension, status, finished_at)
+VALUES ('distiluse-base-multilingual-cased-v2', 512, 'active', CURRENT_TIMESTAMP)
+ON CONFLICT DO NOTHING;
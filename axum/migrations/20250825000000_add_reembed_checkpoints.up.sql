@@ -0,0 +1,10 @@
+-- Progress of `neural_notes_axum reembed` runs so an interrupted run resumes
+-- after the last committed batch
+CREATE TABLE IF NOT EXISTS reembed_checkpoints (
+    run_key TEXT PRIMARY KEY,
+    last_id BIGINT NOT NULL,
+    done BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
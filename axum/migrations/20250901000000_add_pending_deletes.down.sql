@@ -0,0 +1 @@
+ALTER TABLE pending_writes DROP COLUMN IF EXISTS deleted;
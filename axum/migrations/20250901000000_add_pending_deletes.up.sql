@@ -0,0 +1,2 @@
+-- Deletes absorbed by the write-back cache are journaled next to updates
+ALTER TABLE pending_writes ADD COLUMN IF NOT EXISTS deleted BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,10 @@
+-- Users whose clusters were computed, including those without any embedded post
+CREATE TABLE IF NOT EXISTS cluster_runs (
+    user_id BIGINT PRIMARY KEY,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+INSERT INTO cluster_runs (user_id)
+SELECT DISTINCT user_id FROM clusters
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,11 @@
+-- Posts the re-embedding worker failed on, per target model; a post is skipped
+-- once it failed too often on the same content (see src/posts/reembed.rs)
+CREATE TABLE IF NOT EXISTS reembed_failures (
+    post_id BIGINT NOT NULL,
+    model TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    attempts INT NOT NULL,
+    last_error TEXT,
+    PRIMARY KEY (post_id, model),
+    FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE reembed_checkpoints DROP COLUMN IF EXISTS skipped;
@@ -0,0 +1,2 @@
+-- Posts edited while `reembed` was embedding them are skipped, not overwritten
+ALTER TABLE reembed_checkpoints ADD COLUMN IF NOT EXISTS skipped BIGINT NOT NULL DEFAULT 0;
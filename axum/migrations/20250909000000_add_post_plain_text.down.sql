@@ -0,0 +1 @@
+DROP FUNCTION IF EXISTS post_plain_text(TEXT);
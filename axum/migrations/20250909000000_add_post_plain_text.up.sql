@@ -0,0 +1,15 @@
+-- Post content without HTML tags and common entities, whitespace collapsed,
+-- so search phrases match across markup (see sql/search_posts.sql)
+CREATE OR REPLACE FUNCTION post_plain_text(html TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE AS $$
+    SELECT regexp_replace(
+        replace(replace(replace(replace(replace(replace(
+            regexp_replace(html, '<[^>]*>', ' ', 'g'),
+            '&nbsp;', ' '),
+            '&lt;', '<'),
+            '&gt;', '>'),
+            '&quot;', '"'),
+            '&#39;', ''''),
+            '&amp;', '&'),
+        '\s+', ' ', 'g')
+$$;
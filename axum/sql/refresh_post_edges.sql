@@ -0,0 +1,14 @@
+INSERT INTO post_edges (source_id, target_id, user_id, similarity)
+SELECT p1.id, p2.id, p1.user_id, 1 - (p1.embedding <=> p2.embedding) / 2
+FROM posts p1
+JOIN LATERAL (
+    SELECT id, embedding
+    FROM posts p2
+    WHERE p2.user_id = p1.user_id
+    AND p2.id != p1.id
+    AND p2.embedding IS NOT NULL
+    ORDER BY p2.embedding <=> p1.embedding
+    LIMIT $2
+) p2 ON TRUE
+WHERE p1.id = ANY($1)
+AND p1.embedding IS NOT NULL;
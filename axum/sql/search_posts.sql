@@ -1,5 +1,19 @@
-SELECT id, title, content, created_at, updated_at, user_id, embedding
+SELECT id, title, content, created_at, updated_at, user_id, embedding,
+    COALESCE(1 - (embedding <=> $1) / 2, 0) AS score
 FROM posts
 WHERE user_id = $2
-ORDER BY embedding <=> $1
+AND ($3::timestamptz IS NULL OR created_at >= $3)
+AND ($4::timestamptz IS NULL OR created_at < $4)
+AND ($5::timestamptz IS NULL OR updated_at >= $5)
+AND ($6::timestamptz IS NULL OR updated_at < $6)
+AND title LIKE ALL($7::text[])
+AND NOT title LIKE ANY($8::text[])
+AND content ~* ALL($9::text[])
+AND NOT content ~* ANY($10::text[])
+-- phrase는 태그와 entity를 풀어낸 본문에서 찾음
+AND (title || ' ' || post_plain_text(content)) ILIKE ALL($11::text[])
+AND NOT (title || ' ' || post_plain_text(content)) ILIKE ANY($12::text[])
+AND ($13::bool IS NULL OR (embedding IS NOT NULL) = $13)
+-- 검색어 없이 filter만 있으면 $1이 NULL이므로 최근 수정 순
+ORDER BY embedding <=> $1, updated_at DESC
 LIMIT 10;
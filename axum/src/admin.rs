@@ -0,0 +1,147 @@
+// src/admin.rs
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Json, Path, State},
+    http::{Request, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Postgres, pool::Pool};
+
+use crate::config::Config;
+use crate::posts::dirty::{self, FlushReport};
+use crate::posts::internal_error;
+use crate::posts::reembed::{self, Progress, ReembedError};
+use crate::state::AppState;
+
+/// Operator endpoints, authenticated with `admin.token` instead of user JWTs.
+pub fn routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/admin/cache/flush", post(flush_all))
+        .route("/admin/cache/flush/:id", post(flush_one))
+        .route(
+            "/admin/embeddings/migration",
+            get(migration_progress)
+                .post(start_migration)
+                .delete(cancel_migration),
+        )
+        .route("/admin/embeddings/cutover", post(cutover))
+        .layer(middleware::from_fn_with_state(state, require_admin_token))
+}
+
+async fn require_admin_token(
+    State(config): State<&'static Config>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    // 토큰이 없으면 admin API 자체를 숨김
+    if config.admin.token.is_empty() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if !has_bearer_token(&req, &config.admin.token) {
+        return (StatusCode::UNAUTHORIZED, "Invalid admin token").into_response();
+    }
+    next.run(req).await
+}
+
+/// Whether the request carries `token` as its bearer token.
+pub fn has_bearer_token(req: &Request<Body>, token: &str) -> bool {
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // 길이/내용에 따른 비교 시간 차이를 줄이기 위해 hash끼리 비교
+    Sha256::digest(given.as_bytes()) == Sha256::digest(token.as_bytes())
+}
+
+/// `POST /admin/cache/flush`: writes every dirty post to Postgres now.
+async fn flush_all(
+    State(db): State<Pool<Postgres>>,
+    State(redis): State<redis::Client>,
+    State(config): State<&'static Config>,
+) -> Json<FlushReport> {
+    let report = dirty::flush_all(&db, &redis, config.shutdown.flush_timeout()).await;
+    report.log("admin");
+    Json(report)
+}
+
+/// `POST /admin/cache/flush/:id`: writes one dirty post to Postgres now.
+async fn flush_one(
+    State(db): State<Pool<Postgres>>,
+    State(redis): State<redis::Client>,
+    State(config): State<&'static Config>,
+    Path(id): Path<i64>,
+) -> Result<Json<FlushReport>, (StatusCode, String)> {
+    let report = dirty::flush_one(&db, &redis, id, config.shutdown.flush_timeout())
+        .await
+        .map_err(internal_error)?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Post has no pending writes".to_string(),
+        ))?;
+    report.log("admin");
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct StartMigration {
+    model: String,
+}
+
+fn reembed_error(e: ReembedError) -> (StatusCode, String) {
+    let status = match e {
+        ReembedError::AlreadyRunning(_)
+        | ReembedError::AlreadyActive(_)
+        | ReembedError::Incomplete(_) => StatusCode::CONFLICT,
+        ReembedError::NotRunning => StatusCode::NOT_FOUND,
+        ReembedError::Unsupported | ReembedError::Embedding(_) => StatusCode::BAD_GATEWAY,
+        ReembedError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+/// `GET /admin/embeddings/migration`: progress of the running or last model switch.
+async fn migration_progress(
+    State(db): State<Pool<Postgres>>,
+) -> Result<Json<Progress>, (StatusCode, String)> {
+    reembed::progress(&db)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "No embedding model switch yet".to_string(),
+        ))
+}
+
+/// `POST /admin/embeddings/migration`: starts re-embedding every post with `model`
+/// in the background.
+async fn start_migration(
+    State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    Json(body): Json<StartMigration>,
+) -> Result<(StatusCode, Json<Progress>), (StatusCode, String)> {
+    let progress = reembed::start(&db, &config.embedding, &body.model)
+        .await
+        .map_err(reembed_error)?;
+    Ok((StatusCode::ACCEPTED, Json(progress)))
+}
+
+/// `DELETE /admin/embeddings/migration`: abandons the running switch.
+async fn cancel_migration(
+    State(db): State<Pool<Postgres>>,
+) -> Result<Json<Progress>, (StatusCode, String)> {
+    reembed::cancel(&db).await.map(Json).map_err(reembed_error)
+}
+
+/// `POST /admin/embeddings/cutover`: makes the new model's embeddings live.
+async fn cutover(State(db): State<Pool<Postgres>>) -> Result<Json<Progress>, (StatusCode, String)> {
+    reembed::cutover(&db).await.map(Json).map_err(reembed_error)
+}
@@ -1,14 +1,24 @@
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    extract::{ConnectInfo, Json, State},
+    http::{HeaderMap, StatusCode},
 };
-use bcrypt::verify;
+use bcrypt::{DEFAULT_COST, hash, verify};
 use jsonwebtoken::{EncodingKey, Header, encode};
+use metrics::counter;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use sqlx::{Postgres, pool::Pool};
+use std::net::SocketAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
 
+use crate::config::{Config, JwtConfig};
 use crate::models::User;
+use crate::rate_limit::{LoginLimiter, client_ip};
+
+// 존재하지 않는 username도 bcrypt 검증 시간을 동일하게 소비하기 위한 해시
+static DUMMY_PASSWORD_HASH: Lazy<String> =
+    Lazy::new(|| hash("neural-notes-dummy-password", DEFAULT_COST).expect("dummy hash"));
 
 #[derive(Serialize)]
 struct TokenClaims {
@@ -36,52 +46,114 @@ pub struct UserLogin {
 
 #[derive(serde::Serialize)]
 pub struct AccessToken {
-    access_token: String,
+    pub access_token: String,
 }
-pub async fn init_auth() -> Authorizer<UserClaims> {
-    JwtAuthorizer::from_secret(&std::env::var("JWT_SECRET").expect("JWT_SECRET not set"))
+pub async fn init_auth(config: &JwtConfig) -> Authorizer<UserClaims> {
+    JwtAuthorizer::from_secret(&config.secret)
         .build()
         .await
         .unwrap()
 }
 pub async fn login(
     State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    State(limiter): State<LoginLimiter>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<UserLogin>,
 ) -> Result<Json<AccessToken>, (StatusCode, String)> {
+    let ip = client_ip(&headers, &addr, &config.trusted_proxies);
+
+    if let Err(retry_after) = limiter.check(&ip, &payload.username).await {
+        // 잠긴 상태에서 요청마다 DB write가 생기지 않도록 audit row 대신 metric만 기록
+        counter!("login_rejections_total", "reason" => "rate_limited").increment(1);
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("too many login attempts, retry in {retry_after}s"),
+        ));
+    }
+
     //db에서 찾기
-    let user: User = sqlx::query_as("SELECT * FROM users WHERE username = $1")
-        .bind(payload.username)
-        .fetch_one(&db)
+    let user: Option<User> = sqlx::query_as("SELECT * FROM users WHERE username = $1")
+        .bind(&payload.username)
+        .fetch_optional(&db)
         .await
-        .unwrap();
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     // 비밀번호 검증 (DB hash vs 입력값)
-    let valid = verify(&payload.password, &user.password)
+    // username이 없어도 더미 해시로 검증해서 응답 시간으로 계정 존재 여부를 알 수 없게 함
+    let password_hash = user
+        .as_ref()
+        .map(|user| user.password.as_str())
+        .unwrap_or(DUMMY_PASSWORD_HASH.as_str());
+    let valid = verify(&payload.password, password_hash)
         .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "hash error".to_string()))?;
 
-    if !valid {
-        return Err((StatusCode::UNAUTHORIZED, "invalid password".to_string()));
-    }
+    let user = match user {
+        Some(user) if valid => user,
+        user => {
+            let reason = if user.is_some() {
+                "invalid_password"
+            } else {
+                "unknown_user"
+            };
+            limiter.record_failure(&ip, &payload.username).await;
+            record_failed_login(&db, &payload.username, &ip, user.map(|u| u.id), reason).await;
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "invalid username or password".to_string(),
+            ));
+        }
+    };
+    limiter.record_success(&user.username).await;
+
+    Ok(Json(AccessToken {
+        access_token: issue_access_token(&config.jwt, user.id, &user.username)?,
+    }))
+}
 
+/// Signs a local access token for the given user.
+pub fn issue_access_token(
+    config: &JwtConfig,
+    user_id: i64,
+    username: &str,
+) -> Result<String, (StatusCode, String)> {
     //jwt token 발급
-    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET not set");
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    let exp = now + 6000; //1h
+    let exp = now + config.lifetime_secs;
     let claims = TokenClaims {
-        sub: user.id,
-        username: user.username.to_string(),
+        sub: user_id,
+        username: username.to_string(),
         exp: exp as usize,
     };
-    let token = encode(
+    encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
+        &EncodingKey::from_secret(config.secret.as_bytes()),
     )
-    .unwrap();
-    Ok(Json(AccessToken {
-        access_token: token,
-    }))
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn record_failed_login(
+    db: &Pool<Postgres>,
+    username: &str,
+    ip: &str,
+    user_id: Option<i64>,
+    reason: &str,
+) {
+    if let Err(e) = sqlx::query(
+        "INSERT INTO login_attempts (username, ip, user_id, reason) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(username)
+    .bind(ip)
+    .bind(user_id)
+    .bind(reason)
+    .execute(db)
+    .await
+    {
+        warn!(error = %e, "Failed to record login attempt");
+    }
 }
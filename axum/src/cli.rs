@@ -0,0 +1,157 @@
+// src/cli.rs
+
+use crate::posts::reembed::ReembedOptions;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+pub const USAGE: &str = "\
+usage: neural_notes_axum [reembed [--user ID] [--only-missing] [--concurrency N] [--dry-run] [--restart]]
+
+without a subcommand the HTTP server is started
+
+reembed          re-embed posts with the active embedding model
+  --user ID        only posts of this user
+  --only-missing   only posts without an embedding from the active model
+  --concurrency N  embedding API calls in flight (default 4)
+  --dry-run        print how many posts would be re-embedded and exit
+  --restart        ignore the checkpoint of an interrupted run";
+
+/// One-off commands run instead of the server.
+#[derive(Debug)]
+pub enum Command {
+    Reembed(ReembedOptions),
+}
+
+/// Parses the process arguments; `Ok(None)` means "run the server".
+pub fn parse() -> Result<Option<Command>, String> {
+    parse_args(std::env::args().skip(1))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Command>, String> {
+    let Some(command) = args.next() else {
+        return Ok(None);
+    };
+    match command.as_str() {
+        "reembed" => parse_reembed(args).map(|options| Some(Command::Reembed(options))),
+        other => Err(format!("unknown command `{other}`")),
+    }
+}
+
+fn parse_reembed(mut args: impl Iterator<Item = String>) -> Result<ReembedOptions, String> {
+    let mut options = ReembedOptions {
+        user_id: None,
+        only_missing: false,
+        concurrency: DEFAULT_CONCURRENCY,
+        dry_run: false,
+        restart: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--user" => options.user_id = Some(value(&arg, args.next())?),
+            "--concurrency" => {
+                options.concurrency = value(&arg, args.next())?;
+                if options.concurrency == 0 {
+                    return Err("--concurrency must be at least 1".to_string());
+                }
+            }
+            "--only-missing" => options.only_missing = true,
+            "--dry-run" => options.dry_run = true,
+            "--restart" => options.restart = true,
+            other => return Err(format!("unknown option `{other}`")),
+        }
+    }
+    Ok(options)
+}
+
+fn value<T: std::str::FromStr>(flag: &str, raw: Option<String>) -> Result<T, String> {
+    let raw = raw.ok_or_else(|| format!("{flag} needs a value"))?;
+    raw.parse()
+        .map_err(|_| format!("invalid value `{raw}` for {flag}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Command>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn reembed(args: &[&str]) -> Result<ReembedOptions, String> {
+        match parse(args)? {
+            Some(Command::Reembed(options)) => Ok(options),
+            None => panic!("expected a command"),
+        }
+    }
+
+    #[test]
+    fn no_arguments_runs_the_server() {
+        assert!(matches!(parse(&[]), Ok(None)));
+    }
+
+    #[test]
+    fn reembed_defaults() {
+        assert_eq!(
+            reembed(&["reembed"]),
+            Ok(ReembedOptions {
+                user_id: None,
+                only_missing: false,
+                concurrency: DEFAULT_CONCURRENCY,
+                dry_run: false,
+                restart: false,
+            })
+        );
+    }
+
+    #[test]
+    fn reembed_flags() {
+        assert_eq!(
+            reembed(&[
+                "reembed",
+                "--user",
+                "42",
+                "--only-missing",
+                "--concurrency",
+                "8",
+                "--dry-run",
+                "--restart",
+            ]),
+            Ok(ReembedOptions {
+                user_id: Some(42),
+                only_missing: true,
+                concurrency: 8,
+                dry_run: true,
+                restart: true,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_invalid_values() {
+        assert_eq!(
+            reembed(&["reembed", "--user"]).unwrap_err(),
+            "--user needs a value"
+        );
+        assert_eq!(
+            reembed(&["reembed", "--user", "abc"]).unwrap_err(),
+            "invalid value `abc` for --user"
+        );
+        assert_eq!(
+            reembed(&["reembed", "--concurrency", "-1"]).unwrap_err(),
+            "invalid value `-1` for --concurrency"
+        );
+        assert_eq!(
+            reembed(&["reembed", "--concurrency", "0"]).unwrap_err(),
+            "--concurrency must be at least 1"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_options() {
+        assert_eq!(parse(&["serve"]).unwrap_err(), "unknown command `serve`");
+        assert_eq!(
+            reembed(&["reembed", "--fast"]).unwrap_err(),
+            "unknown option `--fast`"
+        );
+    }
+}
@@ -0,0 +1,900 @@
+// src/config.rs
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use sqlx::postgres::PgSslMode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("failed to parse config file {path}: {source}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("invalid value for {var}: {value:?} ({reason})")]
+    InvalidEnv {
+        var: &'static str,
+        value: String,
+        reason: String,
+    },
+    #[error("missing required setting `{0}`")]
+    Missing(&'static str),
+    #[error("invalid setting `{0}`: {1}")]
+    Invalid(&'static str, String),
+}
+
+/// Application settings, loaded once at startup.
+///
+/// Values come from built-in defaults, then an optional TOML file
+/// (`CONFIG_FILE`, or `./config.toml` if present), then environment variables.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub listen_addr: SocketAddr,
+    /// Reverse proxies whose `X-Forwarded-For` is trusted for the client address.
+    pub trusted_proxies: Vec<IpAddr>,
+    pub redis_url: String,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub embedding: EmbeddingConfig,
+    pub similarity: SimilarityConfig,
+    pub log: LogConfig,
+    pub shutdown: ShutdownConfig,
+    pub journal: JournalConfig,
+    pub cache: CacheConfig,
+    pub flush: FlushConfig,
+    pub admin: AdminConfig,
+    pub metrics: MetricsConfig,
+    pub oidc: Option<OidcConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    pub name: String,
+    pub max_connections: u32,
+    pub ssl_mode: SslMode,
+    /// Connection attempts at startup before giving up (0 = retry forever).
+    pub connect_attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JwtConfig {
+    pub secret: String,
+    pub lifetime_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    pub api_url: String,
+    pub api_key: String,
+    pub max_retries: u32,
+    pub timeout_secs: u64,
+    /// Model used until one is recorded as active in `embedding_models`.
+    pub model: String,
+    /// Posts embedded per transaction while re-embedding into a new model.
+    pub reembed_batch_size: i64,
+    /// How long the batching client waits for more texts before calling `/embed`.
+    pub batch_window_ms: u64,
+    /// Maximum texts per `/embed` call.
+    pub max_batch_size: usize,
+    /// Search query embeddings kept in memory per instance.
+    pub query_cache_size: usize,
+    /// How long search query embeddings stay in Redis.
+    pub query_cache_ttl_secs: u64,
+    /// How often the background monitor probes `/health`.
+    pub health_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SimilarityConfig {
+    /// Minimum cosine similarity (0.0 ~ 1.0) for related posts and graph links.
+    pub min_similarity: f64,
+    /// Number of related posts returned with a post.
+    pub related_limit: i64,
+}
+
+impl SimilarityConfig {
+    /// Maximum cosine distance (0.0 ~ 2.0) matching `min_similarity`.
+    pub fn max_distance(&self) -> f64 {
+        2.0 * (1.0 - self.min_similarity)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// `tracing` filter directive, e.g. `info` or `neural_notes_axum=debug,tower_http=info`.
+    pub level: String,
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Time between the shutdown signal and closing the listener; writes are
+    /// rejected and `/readyz` fails during this period.
+    pub drain_secs: u64,
+    /// Upper bound for flushing dirty cache entries to Postgres, at shutdown
+    /// and in the startup recovery pass.
+    pub flush_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Consecutive cache failures before `/posts/:id` bypasses Redis.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before Redis is probed again.
+    pub open_secs: u64,
+}
+
+/// Flush schedule for posts waiting in the write-back cache.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FlushConfig {
+    pub interval_secs: u64,
+    /// A post dirty for this long is flushed even during an autosave burst.
+    pub max_dirty_age_secs: u64,
+    /// Above this many dirty posts the oldest are flushed immediately.
+    pub max_dirty_entries: usize,
+    /// Quiet time after the last write before a post is flushed.
+    pub debounce_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Bearer token for `/admin/*`; the admin API is disabled when empty.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Serves `/metrics` on this separate address instead of `listen_addr`,
+    /// e.g. a port only reachable from inside the cluster.
+    pub listen_addr: Option<SocketAddr>,
+    /// Optional bearer token for `/metrics`; open when empty.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    pub mode: Durability,
+    pub backend: JournalBackend,
+    /// Journal file for the `file` backend.
+    pub path: String,
+}
+
+/// `fast` acknowledges writes once Redis has them; `durable` journals them first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Durability {
+    Fast,
+    Durable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalBackend {
+    File,
+    Postgres,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub post_login_redirect: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            trusted_proxies: Vec::new(),
+            redis_url: String::new(),
+            database: DatabaseConfig::default(),
+            jwt: JwtConfig::default(),
+            embedding: EmbeddingConfig::default(),
+            similarity: SimilarityConfig::default(),
+            log: LogConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            journal: JournalConfig::default(),
+            cache: CacheConfig::default(),
+            flush: FlushConfig::default(),
+            admin: AdminConfig::default(),
+            metrics: MetricsConfig::default(),
+            oidc: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 5432,
+            user: String::new(),
+            password: String::new(),
+            name: String::new(),
+            max_connections: 5,
+            connect_attempts: 20,
+            // 운영(release)에서 설정을 빠뜨려도 평문으로 떨어지지 않도록
+            ssl_mode: if cfg!(debug_assertions) {
+                SslMode::Disable
+            } else {
+                SslMode::Require
+            },
+        }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            lifetime_secs: 6000,
+        }
+    }
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "http://embed_api:8001".to_string(),
+            api_key: String::new(),
+            max_retries: 2,
+            timeout_secs: 30,
+            model: "distiluse-base-multilingual-cased-v2".to_string(),
+            reembed_batch_size: 16,
+            batch_window_ms: 10,
+            max_batch_size: 32,
+            query_cache_size: 1024,
+            query_cache_ttl_secs: 24 * 60 * 60,
+            health_interval_secs: 10,
+        }
+    }
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        Self {
+            min_similarity: 0.5,
+            related_limit: 3,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Pretty,
+        }
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_secs: 5,
+            flush_timeout_secs: 20,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            open_secs: 30,
+        }
+    }
+}
+
+impl Default for FlushConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5,
+            max_dirty_age_secs: 60,
+            max_dirty_entries: 1000,
+            debounce_ms: 2000,
+        }
+    }
+}
+
+impl FlushConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            mode: Durability::Fast,
+            backend: JournalBackend::Postgres,
+            path: "data/pending_writes.jsonl".to_string(),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn drain(&self) -> Duration {
+        Duration::from_secs(self.drain_secs)
+    }
+
+    pub fn flush_timeout(&self) -> Duration {
+        Duration::from_secs(self.flush_timeout_secs)
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("expected pretty or json, got {other}")),
+        }
+    }
+}
+
+impl FromStr for SslMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "disable" => Ok(SslMode::Disable),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            other => Err(format!("expected disable, prefer or require, got {other}")),
+        }
+    }
+}
+
+impl FromStr for Durability {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "fast" => Ok(Durability::Fast),
+            "durable" => Ok(Durability::Durable),
+            other => Err(format!("expected fast or durable, got {other}")),
+        }
+    }
+}
+
+impl FromStr for JournalBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "file" => Ok(JournalBackend::File),
+            "postgres" => Ok(JournalBackend::Postgres),
+            other => Err(format!("expected file or postgres, got {other}")),
+        }
+    }
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match config_file_path() {
+            Some(path) => {
+                let raw = std::fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+                toml::from_str(&raw).map_err(|source| ConfigError::Parse { path, source })?
+            }
+            None => Config::default(),
+        };
+        config.apply_env(&|var| std::env::var(var).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_env(&mut self, env: &Env<'_>) -> Result<(), ConfigError> {
+        env_override(env, &mut self.listen_addr, "LISTEN_ADDR")?;
+        env_list(env, &mut self.trusted_proxies, "TRUSTED_PROXIES")?;
+        env_override(env, &mut self.redis_url, "REDIS_URL")?;
+
+        let db = &mut self.database;
+        env_override(env, &mut db.host, "DATABASE_HOST")?;
+        env_override(env, &mut db.port, "DATABASE_PORT")?;
+        env_override(env, &mut db.user, "DATABASE_USER")?;
+        env_override(env, &mut db.password, "DATABASE_PASSWORD")?;
+        env_override(env, &mut db.name, "DATABASE_NAME")?;
+        env_override(env, &mut db.max_connections, "DATABASE_MAX_CONNECTIONS")?;
+        env_override(env, &mut db.ssl_mode, "DATABASE_SSL_MODE")?;
+        env_override(env, &mut db.connect_attempts, "DATABASE_CONNECT_ATTEMPTS")?;
+
+        env_override(env, &mut self.jwt.secret, "JWT_SECRET")?;
+        env_override(env, &mut self.jwt.lifetime_secs, "JWT_LIFETIME_SECS")?;
+
+        let embedding = &mut self.embedding;
+        env_override(env, &mut embedding.api_url, "EMBED_API_URL")?;
+        env_override(env, &mut embedding.api_key, "EMBED_API_KEY")?;
+        env_override(env, &mut embedding.max_retries, "EMBED_MAX_RETRIES")?;
+        env_override(env, &mut embedding.timeout_secs, "EMBED_TIMEOUT_SECS")?;
+        env_override(env, &mut embedding.model, "EMBED_MODEL")?;
+        env_override(
+            env,
+            &mut embedding.reembed_batch_size,
+            "EMBED_REEMBED_BATCH_SIZE",
+        )?;
+        env_override(env, &mut embedding.batch_window_ms, "EMBED_BATCH_WINDOW_MS")?;
+        env_override(env, &mut embedding.max_batch_size, "EMBED_MAX_BATCH_SIZE")?;
+        env_override(
+            env,
+            &mut embedding.query_cache_size,
+            "EMBED_QUERY_CACHE_SIZE",
+        )?;
+        env_override(
+            env,
+            &mut embedding.query_cache_ttl_secs,
+            "EMBED_QUERY_CACHE_TTL_SECS",
+        )?;
+        env_override(
+            env,
+            &mut embedding.health_interval_secs,
+            "EMBED_HEALTH_INTERVAL_SECS",
+        )?;
+
+        env_override(env, &mut self.similarity.min_similarity, "MIN_SIMILARITY")?;
+        env_override(
+            env,
+            &mut self.similarity.related_limit,
+            "RELATED_POSTS_LIMIT",
+        )?;
+
+        env_override(env, &mut self.log.level, "LOG_LEVEL")?;
+        env_override(env, &mut self.log.format, "LOG_FORMAT")?;
+
+        env_override(env, &mut self.shutdown.drain_secs, "SHUTDOWN_DRAIN_SECS")?;
+        env_override(
+            env,
+            &mut self.shutdown.flush_timeout_secs,
+            "SHUTDOWN_FLUSH_TIMEOUT_SECS",
+        )?;
+
+        env_override(env, &mut self.journal.mode, "JOURNAL_MODE")?;
+        env_override(env, &mut self.journal.backend, "JOURNAL_BACKEND")?;
+        env_override(env, &mut self.journal.path, "JOURNAL_PATH")?;
+
+        env_override(
+            env,
+            &mut self.cache.failure_threshold,
+            "CACHE_BREAKER_FAILURE_THRESHOLD",
+        )?;
+        env_override(env, &mut self.cache.open_secs, "CACHE_BREAKER_OPEN_SECS")?;
+
+        let flush = &mut self.flush;
+        env_override(env, &mut flush.interval_secs, "FLUSH_INTERVAL_SECS")?;
+        env_override(
+            env,
+            &mut flush.max_dirty_age_secs,
+            "FLUSH_MAX_DIRTY_AGE_SECS",
+        )?;
+        env_override(env, &mut flush.max_dirty_entries, "FLUSH_MAX_DIRTY_ENTRIES")?;
+        env_override(env, &mut flush.debounce_ms, "FLUSH_DEBOUNCE_MS")?;
+
+        env_override(env, &mut self.admin.token, "ADMIN_TOKEN")?;
+        if let Some(addr) = env("METRICS_LISTEN_ADDR") {
+            // 빈 값이면 기본 listener에서 제공
+            self.metrics.listen_addr = if addr.is_empty() {
+                None
+            } else {
+                Some(addr.parse().map_err(|e: std::net::AddrParseError| {
+                    ConfigError::InvalidEnv {
+                        var: "METRICS_LISTEN_ADDR",
+                        value: addr.clone(),
+                        reason: e.to_string(),
+                    }
+                })?)
+            };
+        }
+        env_override(env, &mut self.metrics.token, "METRICS_TOKEN")?;
+
+        if let Some(issuer_url) = env("OIDC_ISSUER_URL") {
+            let oidc = OidcConfig {
+                issuer_url,
+                client_id: env("OIDC_CLIENT_ID").ok_or(ConfigError::Missing("OIDC_CLIENT_ID"))?,
+                client_secret: env("OIDC_CLIENT_SECRET"),
+                redirect_url: env("OIDC_REDIRECT_URL")
+                    .ok_or(ConfigError::Missing("OIDC_REDIRECT_URL"))?,
+                post_login_redirect: env("OIDC_POST_LOGIN_REDIRECT"),
+            };
+            self.oidc = Some(oidc);
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (name, value) in [
+            ("redis_url (REDIS_URL)", &self.redis_url),
+            ("database.host (DATABASE_HOST)", &self.database.host),
+            ("database.user (DATABASE_USER)", &self.database.user),
+            (
+                "database.password (DATABASE_PASSWORD)",
+                &self.database.password,
+            ),
+            ("database.name (DATABASE_NAME)", &self.database.name),
+            ("jwt.secret (JWT_SECRET)", &self.jwt.secret),
+        ] {
+            if value.is_empty() {
+                return Err(ConfigError::Missing(name));
+            }
+        }
+        if self.database.max_connections == 0 {
+            return Err(ConfigError::Invalid(
+                "database.max_connections",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.jwt.lifetime_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "jwt.lifetime_secs",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.similarity.min_similarity) {
+            return Err(ConfigError::Invalid(
+                "similarity.min_similarity",
+                "must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        if self.similarity.related_limit < 0 {
+            return Err(ConfigError::Invalid(
+                "similarity.related_limit",
+                "must not be negative".to_string(),
+            ));
+        }
+        if self.cache.failure_threshold == 0 {
+            return Err(ConfigError::Invalid(
+                "cache.failure_threshold",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.flush.interval_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "flush.interval_secs",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.embedding.max_retries == 0 {
+            return Err(ConfigError::Invalid(
+                "embedding.max_retries",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.embedding.max_batch_size == 0 {
+            return Err(ConfigError::Invalid(
+                "embedding.max_batch_size",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.embedding.query_cache_size == 0 {
+            return Err(ConfigError::Invalid(
+                "embedding.query_cache_size",
+                "must be at least 1".to_string(),
+            ));
+        }
+        // Redis는 SET EX 0을 거부함
+        if self.embedding.query_cache_ttl_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "embedding.query_cache_ttl_secs",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.embedding.health_interval_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "embedding.health_interval_secs",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.embedding.reembed_batch_size < 1 {
+            return Err(ConfigError::Invalid(
+                "embedding.reembed_batch_size",
+                "must be at least 1".to_string(),
+            ));
+        }
+        reqwest::Url::parse(&self.embedding.api_url)
+            .map_err(|e| ConfigError::Invalid("embedding.api_url", e.to_string()))?;
+        Ok(())
+    }
+}
+
+fn config_file_path() -> Option<String> {
+    match std::env::var("CONFIG_FILE") {
+        Ok(path) => Some(path),
+        Err(_) => Path::new("config.toml")
+            .exists()
+            .then(|| "config.toml".to_string()),
+    }
+}
+
+// 환경변수 조회; 테스트는 프로세스 환경 대신 map을 넘김
+type Env<'a> = dyn Fn(&str) -> Option<String> + 'a;
+
+fn env_override<T>(env: &Env<'_>, target: &mut T, var: &'static str) -> Result<(), ConfigError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    if let Some(value) = env(var) {
+        *target = value.parse().map_err(|e: T::Err| ConfigError::InvalidEnv {
+            var,
+            value: value.clone(),
+            reason: e.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Comma-separated list; an empty variable clears the list.
+fn env_list<T>(env: &Env<'_>, target: &mut Vec<T>, var: &'static str) -> Result<(), ConfigError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    if let Some(value) = env(var) {
+        *target = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                item.parse().map_err(|e: T::Err| ConfigError::InvalidEnv {
+                    var,
+                    value: value.clone(),
+                    reason: e.to_string(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(())
+}
+
+/// Loads the configuration once at startup. It lives for the whole process and
+/// is handed out through `AppState` and the `init` functions of background tasks.
+pub fn init() -> Result<&'static Config, ConfigError> {
+    Ok(Box::leak(Box::new(Config::load()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const REQUIRED: &[(&str, &str)] = &[
+        ("REDIS_URL", "redis://localhost:6379"),
+        ("DATABASE_HOST", "localhost"),
+        ("DATABASE_USER", "user"),
+        ("DATABASE_PASSWORD", "password"),
+        ("DATABASE_NAME", "neural_notes"),
+        ("JWT_SECRET", "secret"),
+    ];
+
+    fn load_with(base: Config, vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let vars: HashMap<String, String> = REQUIRED
+            .iter()
+            .chain(vars)
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let mut config = base;
+        config.apply_env(&|var| vars.get(var).cloned())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn load(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        load_with(Config::default(), vars)
+    }
+
+    #[test]
+    fn defaults_need_only_required_settings() {
+        let config = load(&[]).unwrap();
+        assert_eq!(config.database.port, 5432);
+        let ssl_mode = if cfg!(debug_assertions) {
+            SslMode::Disable
+        } else {
+            SslMode::Require
+        };
+        assert_eq!(config.database.ssl_mode, ssl_mode);
+        assert_eq!(config.log.format, LogFormat::Pretty);
+        assert!(config.trusted_proxies.is_empty());
+        assert!(config.oidc.is_none());
+    }
+
+    #[test]
+    fn metrics_listen_addr_from_env() {
+        let config = load(&[("METRICS_LISTEN_ADDR", "0.0.0.0:9090")]).unwrap();
+        assert_eq!(
+            config.metrics.listen_addr,
+            Some("0.0.0.0:9090".parse().unwrap())
+        );
+        assert!(config.metrics.token.is_empty());
+
+        assert!(
+            load(&[("METRICS_LISTEN_ADDR", "")])
+                .unwrap()
+                .metrics
+                .listen_addr
+                .is_none()
+        );
+        assert!(matches!(
+            load(&[("METRICS_LISTEN_ADDR", "nope")]),
+            Err(ConfigError::InvalidEnv {
+                var: "METRICS_LISTEN_ADDR",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn reports_missing_required_setting() {
+        let mut config = Config::default();
+        config.apply_env(&|_| None).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Missing("redis_url (REDIS_URL)"))
+        ));
+    }
+
+    #[test]
+    fn env_overrides_typed_values() {
+        let config = load(&[
+            ("LISTEN_ADDR", "127.0.0.1:8080"),
+            ("DATABASE_PORT", "6543"),
+            ("DATABASE_SSL_MODE", "REQUIRE"),
+            ("MIN_SIMILARITY", "0.7"),
+            ("LOG_FORMAT", "json"),
+            ("EMBED_QUERY_CACHE_SIZE", "16"),
+        ])
+        .unwrap();
+        assert_eq!(config.listen_addr, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(config.database.port, 6543);
+        assert_eq!(config.database.ssl_mode, SslMode::Require);
+        assert_eq!(config.similarity.min_similarity, 0.7);
+        assert_eq!(config.log.format, LogFormat::Json);
+        assert_eq!(config.embedding.query_cache_size, 16);
+    }
+
+    #[test]
+    fn env_wins_over_file() {
+        let file: Config = toml::from_str(
+            r#"
+            redis_url = "redis://file:6379"
+            [database]
+            port = 7000
+            ssl_mode = "disable"
+            "#,
+        )
+        .unwrap();
+        let config = load_with(file, &[("DATABASE_PORT", "7001")]).unwrap();
+        assert_eq!(config.database.port, 7001);
+        // 환경변수가 없는 값은 파일 값 유지
+        assert_eq!(config.database.ssl_mode, SslMode::Disable);
+        assert_eq!(config.redis_url, "redis://localhost:6379");
+    }
+
+    #[test]
+    fn rejects_unparsable_env_value() {
+        let Err(ConfigError::InvalidEnv { var, value, .. }) = load(&[("DATABASE_PORT", "abc")])
+        else {
+            panic!("expected InvalidEnv");
+        };
+        assert_eq!(var, "DATABASE_PORT");
+        assert_eq!(value, "abc");
+        assert!(load(&[("DATABASE_SSL_MODE", "verify-full")]).is_err());
+    }
+
+    #[test]
+    fn parses_trusted_proxy_list() {
+        let config = load(&[("TRUSTED_PROXIES", " 10.0.0.1, ::1 ,")]).unwrap();
+        assert_eq!(
+            config.trusted_proxies,
+            vec![
+                "10.0.0.1".parse::<IpAddr>().unwrap(),
+                "::1".parse().unwrap()
+            ]
+        );
+        assert!(
+            load(&[("TRUSTED_PROXIES", "")])
+                .unwrap()
+                .trusted_proxies
+                .is_empty()
+        );
+        assert!(load(&[("TRUSTED_PROXIES", "10.0.0.1,proxy")]).is_err());
+    }
+
+    #[test]
+    fn oidc_issuer_requires_client_settings() {
+        assert!(matches!(
+            load(&[("OIDC_ISSUER_URL", "https://id.example.com")]),
+            Err(ConfigError::Missing("OIDC_CLIENT_ID"))
+        ));
+        let config = load(&[
+            ("OIDC_ISSUER_URL", "https://id.example.com"),
+            ("OIDC_CLIENT_ID", "blog"),
+            (
+                "OIDC_REDIRECT_URL",
+                "https://blog.example.com/auth/oidc/callback",
+            ),
+        ])
+        .unwrap();
+        let oidc = config.oidc.unwrap();
+        assert_eq!(oidc.client_id, "blog");
+        assert!(oidc.client_secret.is_none());
+    }
+
+    #[test]
+    fn validates_ranges() {
+        assert!(matches!(
+            load(&[("MIN_SIMILARITY", "1.5")]),
+            Err(ConfigError::Invalid("similarity.min_similarity", _))
+        ));
+        assert!(matches!(
+            load(&[("DATABASE_MAX_CONNECTIONS", "0")]),
+            Err(ConfigError::Invalid("database.max_connections", _))
+        ));
+        assert!(matches!(
+            load(&[("EMBED_API_URL", "not a url")]),
+            Err(ConfigError::Invalid("embedding.api_url", _))
+        ));
+        assert!(matches!(
+            load(&[("EMBED_QUERY_CACHE_TTL_SECS", "0")]),
+            Err(ConfigError::Invalid("embedding.query_cache_ttl_secs", _))
+        ));
+    }
+}
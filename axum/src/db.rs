@@ -2,48 +2,43 @@
 
 use sqlx::{
     SqlitePool,
-    postgres::{PgConnectOptions, PgPool, PgPoolOptions, PgSslMode},
+    postgres::{PgConnectOptions, PgPool, PgPoolOptions},
     sqlite::SqliteConnectOptions,
 };
 
 use std::time::Duration;
+use tracing::{error, info, warn};
 
-pub async fn init_db() -> PgPool {
-    let host = std::env::var("DATABASE_HOST").expect("DATABASE_HOST not set");
-    let user = std::env::var("DATABASE_USER").expect("DATABASE_USER not set");
-    let port = std::env::var("DATABASE_PORT")
-        .unwrap_or("5432".to_string())
-        .parse::<u16>()
-        .expect("DATABASE_PORT must be a valid u16");
-    let password = std::env::var("DATABASE_PASSWORD").expect("DATABASE_PASSWORD not set");
-    let database = std::env::var("DATABASE_NAME").expect("DATABASE_NAME not set");
-
-    let ssl_mode = if cfg!(debug_assertions) {
-        PgSslMode::Disable
-    } else {
-        PgSslMode::Require
-    };
+use crate::config::DatabaseConfig;
 
+pub async fn init_db(config: &DatabaseConfig) -> PgPool {
     let conn = PgConnectOptions::new()
-        .host(&host)
-        .port(port)
-        .username(&user)
-        .password(&password)
-        .database(&database)
-        .ssl_mode(ssl_mode);
+        .host(&config.host)
+        .port(config.port)
+        .username(&config.user)
+        .password(&config.password)
+        .database(&config.name)
+        .ssl_mode(config.ssl_mode.into());
 
+    let mut attempt = 0;
     let db = loop {
+        attempt += 1;
         match PgPoolOptions::new()
-            .max_connections(5) // 기존 설정값으로 맞추면 됩니다
+            .max_connections(config.max_connections)
             .connect_with(conn.clone())
             .await
         {
             Ok(pool) => {
-                println!("✅ DB connected successfully.");
+                info!("DB connected successfully.");
                 break pool;
             }
+            Err(e) if config.connect_attempts != 0 && attempt >= config.connect_attempts => {
+                // 무한 대기 대신 종료해서 orchestrator가 재시작하도록 함
+                error!(error = ?e, attempt, "DB connection failed, giving up");
+                std::process::exit(1);
+            }
             Err(e) => {
-                eprintln!("⚠️ DB connection failed, retrying in 3s: {:?}", e);
+                warn!(error = ?e, attempt, "DB connection failed, retrying in 3s");
                 tokio::time::sleep(Duration::from_secs(3)).await;
             }
         }
@@ -0,0 +1,195 @@
+// src/health.rs
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use sqlx::{Postgres, pool::Pool};
+
+use crate::posts::Embedder;
+use crate::shutdown::Drain;
+
+// readiness probe 하나가 너무 오래 걸리지 않도록 의존성마다 제한
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// 심각도 순서 (Ok < Degraded < Unavailable)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+    status: Status,
+    latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Checks {
+    postgres: CheckResult,
+    redis: CheckResult,
+    embedding: CheckResult,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    status: Status,
+    checks: Checks,
+}
+
+#[derive(Serialize)]
+pub struct Liveness {
+    status: Status,
+}
+
+/// `GET /healthz`: the process is up and serving requests.
+pub async fn healthz() -> Json<Liveness> {
+    Json(Liveness { status: Status::Ok })
+}
+
+/// `GET /readyz`: Postgres and Redis are required, the embedding service only degrades
+/// (as last seen by the embedder's background health probe).
+/// Always unavailable once shutdown has started.
+pub async fn readyz(
+    State(db): State<Pool<Postgres>>,
+    State(redis): State<redis::Client>,
+    State(drain): State<Drain>,
+    State(embedder): State<Embedder>,
+) -> (StatusCode, Json<Readiness>) {
+    let (postgres, redis, embedding) = tokio::join!(
+        check(Status::Unavailable, check_postgres(&db)),
+        check(Status::Unavailable, check_redis(&redis)),
+        check(Status::Degraded, check_embedding(&embedder)),
+    );
+
+    let status = if drain.is_draining() {
+        Status::Unavailable
+    } else {
+        [&postgres, &redis, &embedding]
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(Status::Ok)
+    };
+    let code = if status == Status::Unavailable {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    (
+        code,
+        Json(Readiness {
+            status,
+            checks: Checks {
+                postgres,
+                redis,
+                embedding,
+            },
+        }),
+    )
+}
+
+async fn check<F>(on_failure: Status, probe: F) -> CheckResult
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    let latency_ms = started.elapsed().as_millis();
+
+    match result {
+        Ok(()) => CheckResult {
+            status: Status::Ok,
+            latency_ms,
+            error: None,
+        },
+        Err(error) => CheckResult {
+            status: on_failure,
+            latency_ms,
+            error: Some(error),
+        },
+    }
+}
+
+async fn check_postgres(db: &Pool<Postgres>) -> Result<(), String> {
+    sqlx::query("SELECT 1")
+        .execute(db)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn check_redis(client: &redis::Client) -> Result<(), String> {
+    let mut conn = client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+    redis::cmd("PING")
+        .query_async::<String>(&mut conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// 요청마다 호출하지 않고 embedder의 background health probe 결과 사용
+async fn check_embedding(embedder: &Embedder) -> Result<(), String> {
+    if embedder.is_healthy() {
+        Ok(())
+    } else {
+        Err("last health probe failed".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn check_reports_ok() {
+        let result = check(Status::Unavailable, async { Ok(()) }).await;
+        assert_eq!(result.status, Status::Ok);
+        assert_eq!(result.error, None);
+    }
+
+    #[tokio::test]
+    async fn check_uses_failure_status() {
+        let result = check(Status::Degraded, async { Err("refused".to_string()) }).await;
+        assert_eq!(result.status, Status::Degraded);
+        assert_eq!(result.error.as_deref(), Some("refused"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_times_out_slow_probes() {
+        let result = check(Status::Unavailable, async {
+            tokio::time::sleep(CHECK_TIMEOUT * 2).await;
+            Ok(())
+        })
+        .await;
+        assert_eq!(result.status, Status::Unavailable);
+        assert_eq!(result.error.as_deref(), Some("timed out after 2s"));
+    }
+
+    #[test]
+    fn status_orders_by_severity() {
+        let worst = [Status::Ok, Status::Unavailable, Status::Degraded]
+            .into_iter()
+            .max();
+        assert_eq!(worst, Some(Status::Unavailable));
+        assert_eq!(
+            serde_json::to_string(&Status::Degraded).unwrap(),
+            "\"degraded\""
+        );
+    }
+}
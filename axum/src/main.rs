@@ -1,55 +1,92 @@
 use axum::{
     Router,
-    http::{Request, Response, StatusCode},
-    middleware::{Next, from_fn},
+    middleware::{from_fn, from_fn_with_state},
 };
 
-use sqlx::{Postgres, pool::Pool};
-use tower_http::cors::CorsLayer;
+use std::net::SocketAddr;
+use tower_http::{
+    LatencyUnit,
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::{Level, error, info, warn};
 
 //auth
 use axum::routing::{delete, get, post, put};
 use jwt_authorizer::IntoLayer;
 use serde::{Deserialize, Serialize};
+mod admin;
 mod auth;
+mod cli;
+mod config;
 use auth::login;
 use dotenv::dotenv;
 
 mod db;
+mod health;
 use db::init_db;
 
 mod models;
+mod monitoring;
+mod oidc;
+mod rate_limit;
 mod routes;
+mod shutdown;
+mod state;
+mod telemetry;
+mod tokens;
+use state::AppState;
 
 mod posts;
 
-use axum::body::Body;
-
-async fn middleware_logger(req: Request<Body>, next: Next) -> Result<Response<Body>, StatusCode> {
-    println!("→ incoming request: {}", req.uri());
-
-    let res = next.run(req).await;
-
-    println!("← outgoing response: {}", res.status());
-    Ok(res)
-}
-
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    let db = init_db().await;
-    let auth = auth::init_auth().await;
+    let command = cli::parse().unwrap_or_else(|e| {
+        eprintln!("❌ {e}\n\n{}", cli::USAGE);
+        std::process::exit(2);
+    });
+    let config = config::init().unwrap_or_else(|e| {
+        eprintln!("❌ Invalid configuration: {e}");
+        std::process::exit(1);
+    });
+    telemetry::init(&config.log);
+    let db = init_db(&config.database).await;
+    if let Some(command) = command {
+        run_command(command, db, config).await;
+        return;
+    }
+    let auth = auth::init_auth(&config.jwt).await;
     //let cache_connection = axum_redis_cache::CacheConnection::new(db.clone()).await;
-    let cacheconnconfig = axum_redis_cache::CacheConnConfig::new().with_url(
-        std::env::var("REDIS_URL")
-            .expect("REDIS_URL must be set")
-            .as_str(),
-    );
+    let cacheconnconfig =
+        axum_redis_cache::CacheConnConfig::new().with_url(config.redis_url.as_str());
 
     let cache_connection =
         axum_redis_cache::CacheConnection::new_with_config(db.clone(), cacheconnconfig).await;
 
-    let key = String::from("posts");
+    let redis = redis::Client::open(config.redis_url.as_str()).expect("invalid REDIS_URL");
+    posts::query_cache::init(redis.clone(), &config.embedding);
+    let embedder = posts::embedder::init(&config.embedding);
+    posts::dirty::init(redis.clone());
+    posts::related::init(db.clone(), redis.clone(), &config.similarity);
+    posts::graph::init(redis.clone());
+    posts::cluster::init(db.clone());
+    // 이전 실행에서 진행 중이던 embedding 모델 전환 이어서 진행
+    posts::reembed::spawn_worker(db.clone(), &config.embedding);
+    if let Err(e) = posts::journal::init(&config.journal, &db).await {
+        error!(error = %e, "Failed to open write-ahead journal");
+        std::process::exit(1);
+    }
+    // Redis가 유실된 경우를 위해 journal 먼저 반영
+    posts::journal::replay(&db).await;
+    // 이전 인스턴스가 flush하지 못하고 죽었을 때 남은 write 복구
+    posts::dirty::flush_all(&db, &redis, config.shutdown.flush_timeout())
+        .await
+        .log("startup recovery");
+    posts::dirty::spawn_flusher(db.clone(), redis.clone(), &config.flush);
+
+    let key = String::from(posts::CACHE_KEY);
     let cache_manager = cache_connection.get_manager(
         key,
         posts::callback,
@@ -57,34 +94,145 @@ async fn main() {
         posts::write_to_cache,
     );
 
+    let oidc = oidc::OidcClient::from_config(config.oidc.as_ref(), redis.clone())
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("❌ OIDC discovery failed: {e}");
+            std::process::exit(1);
+        });
+
+    let state = AppState {
+        db: db.clone(),
+        redis: redis.clone(),
+        config,
+        login_limiter: rate_limit::LoginLimiter::new(&config.redis_url).await,
+        oidc,
+        metrics: monitoring::init(),
+        drain: shutdown::Drain::default(),
+        embedder,
+    };
+    let drain = state.drain.clone();
+    monitoring::spawn_db_gauges(db.clone());
+
     let protected_routes = Router::new()
-        .merge(posts::routes(cache_manager.get_state()))
+        .merge(posts::routes(posts::CacheLayerState {
+            cache: cache_manager.get_state(),
+            breaker: posts::CacheBreaker::new(redis.clone(), &config.cache),
+        }))
         .merge(routes::routes())
+        .merge(tokens::routes())
+        .route("/auth/oidc/link", post(oidc::link))
+        .layer(from_fn(telemetry::record_user))
         // adding the authorizer layer
-        .layer(auth.into_layer());
+        .layer(auth.into_layer())
+        // provider(IdP) 발급 토큰을 자체 토큰으로 교환
+        .layer(from_fn_with_state(state.clone(), oidc::provider_tokens))
+        // 개인 API 토큰도 자체 토큰으로 교환
+        .layer(from_fn_with_state(state.clone(), tokens::personal_tokens));
 
-    let public_routes: Router<Pool<Postgres>> = Router::new()
+    let public_routes: Router<AppState> = Router::new()
         .route("/login", post(login))
-        .merge(routes::public_routes());
+        .route("/auth/oidc/login", get(oidc::login))
+        .route("/auth/oidc/callback", get(oidc::callback))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .merge(routes::public_routes())
+        .merge(admin::routes(state.clone()));
+    // /metrics는 별도 주소가 있으면 그쪽에서만, 없으면 기본 listener에서 제공
+    let public_routes = match config.metrics.listen_addr {
+        Some(addr) => {
+            let metrics_app = monitoring::routes(state.clone()).with_state(state.clone());
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            info!("serving metrics on {addr}");
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(listener, metrics_app).await {
+                    error!(error = %e, "Metrics listener failed");
+                }
+            });
+            public_routes
+        }
+        None => public_routes.merge(monitoring::routes(state.clone())),
+    };
 
     let app = Router::new()
         .merge(public_routes)
         .merge(protected_routes)
-        .layer(from_fn(middleware_logger))
+        .layer(from_fn_with_state(state.clone(), shutdown::reject_writes))
+        .layer(from_fn(monitoring::track_http))
+        .layer(from_fn(telemetry::scope_request_id))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::make_span)
+                .on_response(
+                    DefaultOnResponse::new()
+                        .level(Level::INFO)
+                        .latency_unit(LatencyUnit::Millis),
+                ),
+        )
+        .layer(PropagateRequestIdLayer::new(telemetry::REQUEST_ID_HEADER))
+        .layer(SetRequestIdLayer::new(
+            telemetry::REQUEST_ID_HEADER,
+            MakeRequestUuid,
+        ))
         .layer(CorsLayer::permissive())
-        .with_state(db);
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
+        .with_state(state);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async {
-            tokio::signal::ctrl_c()
-                .await
-                .expect("failed to listen for ctrl_c");
-            println!("🔴 Ctrl+C received. Cancelling...");
-        })
+    let listener = tokio::net::TcpListener::bind(config.listen_addr)
         .await
         .unwrap();
+    info!("listening on {}", config.listen_addr);
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown::signal(drain, &config.shutdown))
+    .await
+    .unwrap();
+
+    // in-flight 요청이 끝난 뒤 cache manager 정리, 남은 dirty post는 직접 flush
+    let flush_timeout = config.shutdown.flush_timeout();
+    let started = std::time::Instant::now();
+    if tokio::time::timeout(flush_timeout, cache_manager.shutdown())
+        .await
+        .is_err()
+    {
+        warn!("Cache manager shutdown timed out");
+    }
+    posts::dirty::flush_all(&db, &redis, flush_timeout.saturating_sub(started.elapsed()))
+        .await
+        .log("shutdown");
+}
 
-    cache_manager.shutdown().await;
+async fn run_command(command: cli::Command, db: sqlx::PgPool, config: &'static config::Config) {
+    let redis = redis::Client::open(config.redis_url.as_str()).expect("invalid REDIS_URL");
+    // 서버가 쓰는 캐시도 함께 무효화되도록
+    posts::related::init(db.clone(), redis.clone(), &config.similarity);
+    posts::graph::init(redis);
+    posts::embedder::init(&config.embedding);
+
+    match command {
+        cli::Command::Reembed(options) => {
+            match posts::reembed::run_command(&db, &config.embedding, &options).await {
+                Ok(report) => {
+                    info!(
+                        model = report.model,
+                        selected = report.selected,
+                        done = report.done,
+                        failed = report.failed,
+                        skipped = report.skipped,
+                        dry_run = options.dry_run,
+                        "Re-embed finished"
+                    );
+                    if report.failed > 0 {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Re-embed failed; rerun the same command to resume");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
 }
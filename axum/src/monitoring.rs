@@ -0,0 +1,243 @@
+// src/monitoring.rs
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{MatchedPath, State},
+    http::{Method, Request, StatusCode},
+    middleware::{Next, from_fn_with_state},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::{Postgres, pool::Pool};
+use tracing::warn;
+
+use crate::admin::has_bearer_token;
+use crate::config::Config;
+use crate::state::AppState;
+
+// 초 단위 latency histogram bucket
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+// DB를 세는 gauge 갱신 주기; scrape마다 COUNT(*)를 돌리지 않도록
+const DB_GAUGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Installs the global Prometheus recorder and returns the handle used by `/metrics`.
+pub fn init() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("duration_seconds".to_string()),
+            LATENCY_BUCKETS,
+        )
+        .expect("invalid histogram buckets")
+        .install_recorder()
+        .expect("failed to install Prometheus recorder");
+
+    // histogram 메모리 정리
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
+
+    handle
+}
+
+/// Refreshes gauges that need a database query in the background, so scrapes
+/// stay cheap no matter how often or from how many scrapers they come.
+pub fn spawn_db_gauges(db: Pool<Postgres>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DB_GAUGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM posts WHERE embedding IS NULL")
+                .fetch_one(&db)
+                .await
+            {
+                Ok(count) => gauge!("posts_without_embedding").set(count as f64),
+                Err(e) => warn!(error = %e, "Failed to count posts without embedding"),
+            }
+        }
+    });
+}
+
+/// `GET /metrics`, open unless `metrics.token` is set.
+pub fn routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .layer(from_fn_with_state(state, require_metrics_token))
+}
+
+async fn require_metrics_token(
+    State(config): State<&'static Config>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !config.metrics.token.is_empty() && !has_bearer_token(&req, &config.metrics.token) {
+        return (StatusCode::UNAUTHORIZED, "Invalid metrics token").into_response();
+    }
+    next.run(req).await
+}
+
+/// `GET /metrics` in the Prometheus text format.
+async fn metrics_handler(
+    State(handle): State<PrometheusHandle>,
+    State(db): State<Pool<Postgres>>,
+) -> String {
+    // pool 사용량은 scrape 시점에 갱신
+    let size = db.size() as f64;
+    let idle = db.num_idle() as f64;
+    gauge!("db_pool_connections", "state" => "idle").set(idle);
+    gauge!("db_pool_connections", "state" => "active").set(size - idle);
+    gauge!("db_pool_max_connections").set(db.options().get_max_connections() as f64);
+
+    handle.render()
+}
+
+/// Records request count and latency per method / matched route / status.
+pub async fn track_http(req: Request<Body>, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let res = next.run(req).await;
+
+    let status = res.status().as_u16().to_string();
+    let labels = [("method", method), ("route", route), ("status", status)];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+    res
+}
+
+#[derive(Clone, Default)]
+pub struct ReachedHandler(Arc<AtomicBool>);
+
+impl ReachedHandler {
+    /// Whether the request got past the cache middleware to a handler.
+    pub fn get(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Wraps the write-back cache middleware to count hits, misses and absorbed writes.
+///
+/// Works together with [`mark_cache_miss`], which sits between the cache
+/// middleware and the handlers: if the request never reaches it, the cache
+/// answered on its own.
+pub async fn track_cache(mut req: Request<Body>, next: Next) -> Response {
+    let reached = ReachedHandler::default();
+    req.extensions_mut().insert(reached.clone());
+    let method = req.method().clone();
+
+    let res = next.run(req).await;
+
+    let outcome = cache_outcome(&method, !reached.get());
+    counter!("cache_requests_total", "outcome" => outcome).increment(1);
+    res
+}
+
+fn cache_outcome(method: &Method, served_by_cache: bool) -> &'static str {
+    match (*method == Method::GET, served_by_cache) {
+        (true, true) => "hit",
+        (true, false) => "miss",
+        (false, true) => "write_absorbed",
+        (false, false) => "write_through",
+    }
+}
+
+pub async fn mark_cache_miss(req: Request<Body>, next: Next) -> Response {
+    if let Some(reached) = req.extensions().get::<ReachedHandler>() {
+        reached.0.store(true, Ordering::Relaxed);
+    }
+    next.run(req).await
+}
+
+/// Counts write-back flushes from the cache callbacks.
+pub fn record_cache_flush(op: &'static str, ok: bool) {
+    let result = if ok { "ok" } else { "error" };
+    counter!("cache_flush_total", "op" => op, "result" => result).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_outcome_by_method() {
+        assert_eq!(cache_outcome(&Method::GET, true), "hit");
+        assert_eq!(cache_outcome(&Method::GET, false), "miss");
+        assert_eq!(cache_outcome(&Method::PUT, true), "write_absorbed");
+        assert_eq!(cache_outcome(&Method::DELETE, false), "write_through");
+    }
+
+    #[tokio::test]
+    async fn mark_cache_miss_flags_the_request() {
+        let reached = ReachedHandler::default();
+        let mut req = Request::new(Body::empty());
+        req.extensions_mut().insert(reached.clone());
+        assert!(!reached.get());
+
+        let mut app = axum::Router::new()
+            .route("/", axum::routing::get(|| async {}))
+            .layer(axum::middleware::from_fn(mark_cache_miss));
+        tower::Service::call(&mut app, req).await.unwrap();
+        assert!(reached.get());
+    }
+
+    async fn metrics_status(token: &str, header: Option<&str>) -> StatusCode {
+        let mut config = Config::default();
+        config.metrics.token = token.to_string();
+        let config: &'static Config = Box::leak(Box::new(config));
+        let mut app = axum::Router::new()
+            .route("/metrics", get(|| async { "ok" }))
+            .layer(from_fn_with_state(config, require_metrics_token));
+
+        let mut req = Request::builder().uri("/metrics");
+        if let Some(header) = header {
+            req = req.header(axum::http::header::AUTHORIZATION, header);
+        }
+        tower::Service::call(&mut app, req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn metrics_are_open_without_a_token() {
+        assert_eq!(metrics_status("", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn metrics_token_is_enforced_when_set() {
+        assert_eq!(
+            metrics_status("scrape", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            metrics_status("scrape", Some("Bearer wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            metrics_status("scrape", Some("Bearer scrape")).await,
+            StatusCode::OK
+        );
+    }
+}
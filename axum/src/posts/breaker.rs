@@ -0,0 +1,232 @@
+// src/posts/breaker.rs
+
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_redis_cache::CacheState;
+use futures_util::FutureExt;
+use metrics::{counter, gauge};
+use tracing::{error, info, warn};
+
+use super::dirty;
+use crate::config::CacheConfig;
+use crate::monitoring::ReachedHandler;
+
+// half-open 상태에서 Redis 확인용 PING 제한 시간
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum BreakerState {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    // 한 요청이 Redis를 확인하는 동안 나머지는 계속 우회
+    HalfOpen,
+}
+
+/// Circuit breaker around the Redis write-back cache for `/posts/:id`.
+///
+/// After `failure_threshold` consecutive cache failures the cache middleware
+/// is skipped and requests go straight to the DB handlers. Once `open_secs`
+/// have passed, the next request pings Redis and closes the breaker again if
+/// it answers.
+///
+/// A post written while bypassing has its Redis copy discarded (see
+/// [`dirty::discard`]); until that succeeds it keeps bypassing the cache.
+#[derive(Clone)]
+pub struct CacheBreaker {
+    state: Arc<Mutex<BreakerState>>,
+    redis: redis::Client,
+    failure_threshold: u32,
+    open_for: Duration,
+}
+
+#[derive(Clone)]
+pub struct CacheLayerState {
+    pub cache: CacheState,
+    pub breaker: CacheBreaker,
+}
+
+impl CacheBreaker {
+    pub fn new(redis: redis::Client, config: &CacheConfig) -> Self {
+        gauge!("cache_breaker_open").set(0.0);
+        Self {
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: 0 })),
+            redis,
+            failure_threshold: config.failure_threshold,
+            open_for: Duration::from_secs(config.open_secs),
+        }
+    }
+
+    /// Whether this request may go through the cache.
+    async fn allow(&self) -> bool {
+        {
+            let mut state = self.state.lock().unwrap();
+            match *state {
+                BreakerState::Closed { .. } => return true,
+                BreakerState::HalfOpen => return false,
+                BreakerState::Open { since } if since.elapsed() < self.open_for => return false,
+                BreakerState::Open { .. } => *state = BreakerState::HalfOpen,
+            }
+        }
+
+        if self.probe().await {
+            // 우회 중에 쓴 post의 오래된 Redis 사본부터 정리
+            if let Err(e) = dirty::retry_discards(&self.redis).await {
+                warn!(error = %e, "Stale cache entries still queued");
+            }
+            *self.state.lock().unwrap() = BreakerState::Closed { failures: 0 };
+            gauge!("cache_breaker_open").set(0.0);
+            counter!("cache_breaker_transitions_total", "to" => "closed").increment(1);
+            info!("Cache breaker closed, Redis reachable again");
+            true
+        } else {
+            *self.state.lock().unwrap() = BreakerState::Open {
+                since: Instant::now(),
+            };
+            false
+        }
+    }
+
+    async fn probe(&self) -> bool {
+        let ping = async {
+            let mut conn = self.redis.get_multiplexed_async_connection().await?;
+            redis::cmd("PING").query_async::<String>(&mut conn).await
+        };
+        matches!(tokio::time::timeout(PROBE_TIMEOUT, ping).await, Ok(Ok(_)))
+    }
+
+    fn record(&self, failed: bool) {
+        let mut state = self.state.lock().unwrap();
+        let BreakerState::Closed { failures } = &mut *state else {
+            return;
+        };
+        if !failed {
+            *failures = 0;
+            return;
+        }
+
+        *failures += 1;
+        if *failures >= self.failure_threshold {
+            warn!(
+                failures = *failures,
+                open_secs = self.open_for.as_secs(),
+                "Cache breaker tripped, serving posts directly from Postgres"
+            );
+            *state = BreakerState::Open {
+                since: Instant::now(),
+            };
+            gauge!("cache_breaker_open").set(1.0);
+            counter!("cache_breaker_transitions_total", "to" => "open").increment(1);
+        }
+    }
+}
+
+/// Runs the Redis cache middleware unless the breaker is open.
+///
+/// A 5xx answered by the cache itself (the handler was never reached) counts
+/// as a cache failure; handler errors do not.
+pub async fn cache_or_bypass(
+    State(layer): State<CacheLayerState>,
+    Path(id): Path<i64>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let breaker = &layer.breaker;
+    if dirty::is_stale(id) || !breaker.allow().await {
+        counter!("cache_bypassed_requests_total").increment(1);
+        let is_write = req.method() != Method::GET;
+        let res = next.run(req).await;
+        if is_write && res.status().is_success() {
+            // Redis가 죽어 있으면 오래 걸리지 않도록 제한; 실패하면 queue에 남음
+            let _ = tokio::time::timeout(PROBE_TIMEOUT, dirty::discard(&breaker.redis, id)).await;
+        }
+        return res;
+    }
+
+    let reached = req.extensions().get::<ReachedHandler>().cloned();
+    let served_by_cache = || reached.as_ref().is_some_and(|reached| !reached.get());
+    // Redis 오류로 cache middleware가 panic해도 요청 하나만 실패하도록
+    let cached = axum_redis_cache::middleware(State(layer.cache.clone()), req, next);
+    match AssertUnwindSafe(cached).catch_unwind().await {
+        Ok(res) => {
+            breaker.record(served_by_cache() && res.status().is_server_error());
+            res
+        }
+        Err(_) => {
+            error!("Cache middleware panicked");
+            breaker.record(served_by_cache());
+            (StatusCode::SERVICE_UNAVAILABLE, "Cache unavailable").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 연결을 바로 거절하는 주소
+    const UNREACHABLE: &str = "redis://127.0.0.1:1";
+
+    fn breaker(failure_threshold: u32, open_secs: u64) -> CacheBreaker {
+        CacheBreaker::new(
+            redis::Client::open(UNREACHABLE).unwrap(),
+            &CacheConfig {
+                failure_threshold,
+                open_secs,
+            },
+        )
+    }
+
+    fn is_open(breaker: &CacheBreaker) -> bool {
+        matches!(*breaker.state.lock().unwrap(), BreakerState::Open { .. })
+    }
+
+    #[tokio::test]
+    async fn trips_after_consecutive_failures() {
+        let breaker = breaker(3, 60);
+        breaker.record(true);
+        breaker.record(true);
+        assert!(breaker.allow().await);
+        breaker.record(true);
+        assert!(is_open(&breaker));
+        assert!(!breaker.allow().await);
+    }
+
+    #[tokio::test]
+    async fn success_resets_the_failure_streak() {
+        let breaker = breaker(2, 60);
+        breaker.record(true);
+        breaker.record(false);
+        breaker.record(true);
+        assert!(!is_open(&breaker));
+        assert!(breaker.allow().await);
+    }
+
+    #[tokio::test]
+    async fn failed_probe_keeps_the_breaker_open() {
+        let breaker = breaker(1, 0);
+        breaker.record(true);
+        // open_secs가 지나 half-open으로 probe하지만 Redis가 없음
+        assert!(!breaker.allow().await);
+        assert!(is_open(&breaker));
+    }
+
+    #[tokio::test]
+    async fn bypassed_write_stays_stale_while_redis_is_down() {
+        let client = redis::Client::open(UNREACHABLE).unwrap();
+        let id = -35_001;
+        dirty::discard(&client, id).await;
+        assert!(dirty::is_stale(id));
+        assert!(dirty::retry_discards(&client).await.is_err());
+        assert!(dirty::is_stale(id));
+    }
+}
@@ -1,9 +1,24 @@
-use axum::extract::{Json, Path, State};
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::{Postgres, pool::Pool};
+use tracing::{error, info, warn};
 
 use super::handlers::__update_post_from_cache;
 use super::models::{PostResponse, UpdatePost};
+use super::{dirty, graph, journal, related};
+use crate::monitoring::record_cache_flush;
+
+/// Key the write-back cache manager is registered under; it stores post `id`
+/// at `{CACHE_KEY}:{id}`.
+pub const CACHE_KEY: &str = "posts";
+
+pub fn cache_key(id: i64) -> String {
+    format!("{CACHE_KEY}:{id}")
+}
 
 pub fn write_to_cache(old: String, new: String) -> String {
     let parsed_body: UpdatePost = serde_json::from_str(&new).unwrap();
@@ -15,35 +30,81 @@ pub fn write_to_cache(old: String, new: String) -> String {
     if let Some(title) = parsed_body.title {
         payload.title = title;
     }
-    serde_json::to_string(&payload).unwrap()
+    // DB trigger가 flush 시점에 다시 갱신하지만, 그 전까지의 GET도 수정 시각을 반영
+    payload.updated_at = Utc::now();
+    let merged = serde_json::to_string(&payload).unwrap();
+    dirty::mark(payload.id, &merged);
+    merged
 }
 
-pub async fn callback(db: Pool<Postgres>, value: String) {
-    use crate::posts::{PostResponse, UpdatePost};
-    let json: PostResponse = serde_json::from_str(&value).unwrap();
+/// Writes one cached post payload to Postgres and returns its id.
+pub async fn flush_update(db: &Pool<Postgres>, value: &str) -> Result<i64, (StatusCode, String)> {
+    let json: PostResponse = serde_json::from_str(value).map_err(|e| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("invalid cached payload: {e}"),
+        )
+    })?;
     //TODO : from PostResponse, to UpdatePost
     let update_json = UpdatePost {
-        title: Some(json.title),
-        content: Some(json.content),
+        title: Some(json.title.clone()),
+        content: Some(json.content.clone()),
     };
-    let _ = __update_post_from_cache(State(db), Path(json.id), Json(update_json)).await;
+    __update_post_from_cache(State(db.clone()), Path(json.id), Json(update_json)).await?;
+    journal::truncate(json.id, &json.title, &json.content).await;
+    // embedding이 다시 계산됐으므로 related 목록도 갱신
+    related::refresh(json.id).await;
+    Ok(json.id)
+}
+
+/// Flush hook of the write-back cache.
+///
+/// Writes tracked in `posts:dirty` are left to [`dirty::spawn_flusher`], which
+/// applies the configured debounce and age limits under the flush lock; this
+/// only writes payloads the tracker could not record.
+pub async fn callback(db: Pool<Postgres>, value: String) {
+    match dirty::is_tracked(&value).await {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => warn!(error = %e, "Cannot check dirty posts, flushing directly"),
+    }
+    let result = flush_update(&db, &value).await;
+    match &result {
+        Ok(id) => dirty::mark_flushed(*id, &value),
+        Err((_, e)) => error!(error = %e, "write-back flush failed"),
+    }
+    record_cache_flush("update", result.is_ok());
+}
+
+/// Deletes a post whose delete the cache absorbed, and everything derived
+/// from it.
+pub async fn flush_delete(db: &Pool<Postgres>, post_id: i64) -> Result<u64, sqlx::Error> {
+    graph::invalidate_post_graph(db, post_id).await;
+    let sources = graph::edge_sources(db, post_id).await?;
+
+    // 실제 DB에서 삭제
+    let result = sqlx::query("DELETE FROM posts WHERE id = $1")
+        .bind(post_id)
+        .execute(db)
+        .await?;
+    // 삭제된 post를 가리키던 post에 새 neighbor 채움
+    if let Err(e) = graph::refresh_edges(db, &sources).await {
+        error!(post_id, error = %e, "Failed to update graph edges");
+    }
+    related::forget(post_id, true).await;
+    journal::forget(post_id).await;
+    Ok(result.rows_affected())
 }
 
 pub async fn delete_callback(db: Pool<Postgres>, key: String) {
     if let Ok(post_id) = key.parse::<i64>() {
-        println!("🧹 expired 감지됨: delete marker for post_id={}", post_id);
-
-        // 실제 DB에서 삭제
-        let result = sqlx::query("DELETE FROM posts WHERE id = $1")
-            .bind(post_id)
-            .execute(&db)
-            .await
-            .unwrap();
-
-        println!(
-            "✅ DB에서 post {} 삭제 완료 ({} rows affected)",
-            post_id,
-            result.rows_affected()
-        );
+        info!(post_id, "expired 감지됨: delete marker");
+
+        let result = flush_delete(&db, post_id).await;
+        match &result {
+            Ok(rows_affected) => info!(post_id, rows_affected, "DB에서 post 삭제 완료"),
+            Err(e) => error!(post_id, error = %e, "write-back delete failed"),
+        }
+        record_cache_flush("delete", result.is_ok());
     }
 }
@@ -0,0 +1,444 @@
+// src/posts/cluster.rs
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use jwt_authorizer::JwtClaims;
+use once_cell::sync::OnceCell;
+use pgvector::Vector;
+use sqlx::{Postgres, pool::Pool};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use super::graph::invalidate_graph;
+use super::models::{Cluster, ClusterMember};
+use super::recommend::term_counts;
+use super::utils::internal_error;
+use crate::auth::UserClaims;
+
+const MAX_CLUSTERS: usize = 12;
+const KMEANS_ITERATIONS: usize = 25;
+const LABEL_TERMS: usize = 3;
+const STORED_TERMS: usize = 10;
+// 연속된 embedding 변경을 모아서 한 번만 재계산
+const RECLUSTER_DELAY: Duration = Duration::from_secs(30);
+
+static QUEUE: OnceCell<mpsc::UnboundedSender<i64>> = OnceCell::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClusterError {
+    #[error("database error: {0}")]
+    Db(#[from] sqlx::Error),
+    #[error("clustering task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+#[derive(sqlx::FromRow)]
+struct EmbeddedPost {
+    id: i64,
+    title: String,
+    content: String,
+    embedding: Vector,
+}
+
+/// Starts the background worker that reclusters users whose embeddings changed.
+pub fn init(db: Pool<Postgres>) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    if QUEUE.set(tx).is_err() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        while let Some(user_id) = rx.recv().await {
+            tokio::time::sleep(RECLUSTER_DELAY).await;
+            let mut users = HashSet::from([user_id]);
+            while let Ok(user_id) = rx.try_recv() {
+                users.insert(user_id);
+            }
+            for user_id in users {
+                if let Err(e) = recompute(&db, user_id).await {
+                    error!(user_id, error = %e, "Clustering failed");
+                }
+            }
+        }
+    });
+}
+
+/// Queues `user_id` for reclustering (after an embedding changed or a post was deleted).
+pub fn schedule(user_id: i64) {
+    if let Some(queue) = QUEUE.get() {
+        let _ = queue.send(user_id);
+    }
+}
+
+/// `GET /clusters`: topics of the user's notes with their members.
+pub async fn list_clusters(
+    State(db): State<Pool<Postgres>>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+) -> Result<Json<Vec<Cluster>>, (StatusCode, String)> {
+    if let Some(clusters) = load(&db, user.sub).await.map_err(internal_error)? {
+        return Ok(Json(clusters));
+    }
+    // 아직 한 번도 계산되지 않은 사용자
+    recompute(&db, user.sub).await.map_err(internal_error)?;
+    let clusters = load(&db, user.sub).await.map_err(internal_error)?;
+    Ok(Json(clusters.unwrap_or_default()))
+}
+
+// 한 번도 계산되지 않았으면 None
+async fn load(db: &Pool<Postgres>, user_id: i64) -> Result<Option<Vec<Cluster>>, sqlx::Error> {
+    let computed: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM cluster_runs WHERE user_id = $1)")
+            .bind(user_id)
+            .fetch_one(db)
+            .await?;
+    if !computed {
+        return Ok(None);
+    }
+    let rows = sqlx::query_as::<_, (i32, String, Vec<String>)>(
+        "SELECT cluster_id, label, terms FROM clusters WHERE user_id = $1 ORDER BY cluster_id",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    let members = sqlx::query_as::<_, (i32, i64, String)>(
+        r#"
+        SELECT pc.cluster_id, p.id, p.title
+        FROM post_clusters pc
+        JOIN posts p ON p.id = pc.post_id
+        WHERE pc.user_id = $1
+        ORDER BY p.updated_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let mut clusters: BTreeMap<i32, Cluster> = rows
+        .into_iter()
+        .map(|(id, label, terms)| {
+            let cluster = Cluster {
+                id,
+                label,
+                terms,
+                size: 0,
+                posts: Vec::new(),
+            };
+            (id, cluster)
+        })
+        .collect();
+    for (cluster_id, id, title) in members {
+        if let Some(cluster) = clusters.get_mut(&cluster_id) {
+            cluster.size += 1;
+            cluster.posts.push(ClusterMember { id, title });
+        }
+    }
+    Ok(Some(clusters.into_values().collect()))
+}
+
+/// Clusters all embedded posts of `user_id` with spherical k-means and labels
+/// each cluster with its top TF-IDF terms.
+///
+/// Users without embedded posts get an empty result stored, so `GET /clusters`
+/// does not recompute it on every request.
+pub async fn recompute(db: &Pool<Postgres>, user_id: i64) -> Result<(), ClusterError> {
+    let posts = sqlx::query_as::<_, EmbeddedPost>(
+        r#"SELECT id, title, content, embedding
+            FROM posts
+            WHERE user_id = $1
+            AND embedding IS NOT NULL
+            ORDER BY id"#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    // CPU를 오래 쓰므로 async worker 밖에서 계산
+    let (posts, assignment, labels) = tokio::task::spawn_blocking(move || {
+        let (assignment, labels) = cluster_posts(&posts);
+        (posts, assignment, labels)
+    })
+    .await?;
+
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM post_clusters WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM clusters WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    for (cluster_id, terms) in &labels {
+        let label = terms
+            .iter()
+            .take(LABEL_TERMS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(
+            "INSERT INTO clusters (user_id, cluster_id, label, terms) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(*cluster_id as i32)
+        .bind(label)
+        .bind(terms)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let ids: Vec<i64> = posts.iter().map(|post| post.id).collect();
+    let cluster_ids: Vec<i32> = assignment.iter().map(|cluster| *cluster as i32).collect();
+    sqlx::query(
+        r#"
+        INSERT INTO post_clusters (post_id, user_id, cluster_id)
+        SELECT post_id, $1, cluster_id
+        FROM UNNEST($2::BIGINT[], $3::INT[]) AS t(post_id, cluster_id)
+        "#,
+    )
+    .bind(user_id)
+    .bind(&ids)
+    .bind(&cluster_ids)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO cluster_runs (user_id) VALUES ($1)
+        ON CONFLICT (user_id) DO UPDATE SET updated_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    info!(
+        user_id,
+        posts = posts.len(),
+        clusters = labels.len(),
+        "Clusters recomputed"
+    );
+    invalidate_graph(user_id).await;
+    Ok(())
+}
+
+// post별 cluster 번호와 cluster별 TF-IDF 상위 term
+fn cluster_posts(posts: &[EmbeddedPost]) -> (Vec<usize>, BTreeMap<usize, Vec<String>>) {
+    let vectors: Vec<Vec<f32>> = posts
+        .iter()
+        .map(|post| normalize(post.embedding.to_vec()))
+        .collect();
+    let assignment = kmeans(&vectors, cluster_count(posts.len()));
+
+    let documents: Vec<HashMap<String, usize>> = posts
+        .iter()
+        .map(|post| term_counts(&format!("{} {}", post.title, post.content)))
+        .collect();
+    let labels = label_clusters(&documents, &assignment);
+    (assignment, labels)
+}
+
+// 노트 수에 따라 k 결정 (대략 sqrt(n/2))
+fn cluster_count(posts: usize) -> usize {
+    ((posts as f64 / 2.0).sqrt().round() as usize)
+        .clamp(1, MAX_CLUSTERS)
+        .min(posts)
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Spherical k-means over unit vectors; returns the cluster of each vector.
+///
+/// Seeds are picked deterministically (farthest point first) so the same
+/// notes produce the same clusters between runs.
+fn kmeans(vectors: &[Vec<f32>], k: usize) -> Vec<usize> {
+    if vectors.is_empty() || k == 0 {
+        return Vec::new();
+    }
+
+    let mut centroids = vec![vectors[0].clone()];
+    while centroids.len() < k {
+        let farthest = vectors
+            .iter()
+            .max_by(|a, b| {
+                let sim_a = centroids.iter().map(|c| dot(a, c)).fold(f32::MIN, f32::max);
+                let sim_b = centroids.iter().map(|c| dot(b, c)).fold(f32::MIN, f32::max);
+                sim_b.total_cmp(&sim_a)
+            })
+            .expect("vectors is not empty");
+        centroids.push(farthest.clone());
+    }
+
+    let mut assignment = vec![0; vectors.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (i, vector) in vectors.iter().enumerate() {
+            let best = centroids
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| dot(vector, a).total_cmp(&dot(vector, b)))
+                .map(|(cluster, _)| cluster)
+                .unwrap_or(0);
+            if assignment[i] != best {
+                assignment[i] = best;
+                changed = true;
+            }
+        }
+
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0; centroid.len()];
+            for (vector, _) in vectors
+                .iter()
+                .zip(&assignment)
+                .filter(|(_, assigned)| **assigned == cluster)
+            {
+                sum.iter_mut().zip(vector).for_each(|(s, x)| *s += x);
+            }
+            // 빈 cluster는 이전 centroid 유지
+            if sum.iter().any(|x| *x != 0.0) {
+                *centroid = normalize(sum);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // 빈 cluster를 건너뛰고 0부터 다시 번호 매김
+    let mut renumber = BTreeMap::new();
+    for cluster in &assignment {
+        let next = renumber.len();
+        renumber.entry(*cluster).or_insert(next);
+    }
+    assignment.iter().map(|cluster| renumber[cluster]).collect()
+}
+
+/// Top TF-IDF terms per cluster, treating each post as a document.
+fn label_clusters(
+    documents: &[HashMap<String, usize>],
+    assignment: &[usize],
+) -> BTreeMap<usize, Vec<String>> {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for document in documents {
+        for term in document.keys() {
+            *document_frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+    let total = documents.len() as f64;
+
+    let mut scores: BTreeMap<usize, HashMap<&str, f64>> = BTreeMap::new();
+    for (document, cluster) in documents.iter().zip(assignment) {
+        let cluster_scores = scores.entry(*cluster).or_default();
+        for (term, count) in document {
+            let idf = (1.0 + total / document_frequency[term.as_str()] as f64).ln();
+            *cluster_scores.entry(term).or_insert(0.0) += *count as f64 * idf;
+        }
+    }
+
+    scores
+        .into_iter()
+        .map(|(cluster, terms)| {
+            let mut terms: Vec<(&str, f64)> = terms.into_iter().collect();
+            terms.sort_by(|(ta, a), (tb, b)| b.total_cmp(a).then_with(|| ta.cmp(tb)));
+            let top = terms
+                .into_iter()
+                .take(STORED_TERMS)
+                .map(|(term, _)| term.to_string())
+                .collect();
+            (cluster, top)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(text: &str) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for term in text.split_whitespace() {
+            *counts.entry(term.to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn picks_k_from_post_count() {
+        assert_eq!(cluster_count(0), 0);
+        assert_eq!(cluster_count(1), 1);
+        assert_eq!(cluster_count(8), 2);
+        assert_eq!(cluster_count(50), 5);
+        assert_eq!(cluster_count(10_000), MAX_CLUSTERS);
+    }
+
+    #[test]
+    fn separates_distinct_directions() {
+        let vectors: Vec<Vec<f32>> = [
+            [1.0, 0.1, 0.0],
+            [0.9, 0.0, 0.1],
+            [0.0, 1.0, 0.1],
+            [0.1, 0.9, 0.0],
+            [0.0, 0.1, 1.0],
+        ]
+        .iter()
+        .map(|v| normalize(v.to_vec()))
+        .collect();
+        let assignment = kmeans(&vectors, 3);
+        assert_eq!(assignment[0], assignment[1]);
+        assert_eq!(assignment[2], assignment[3]);
+        let distinct: HashSet<usize> = assignment.iter().copied().collect();
+        assert_eq!(distinct.len(), 3);
+        // 첫 post가 속한 cluster가 0
+        assert_eq!(assignment[0], 0);
+    }
+
+    #[test]
+    fn is_deterministic() {
+        let vectors: Vec<Vec<f32>> = (0..20)
+            .map(|i| normalize(vec![(i as f32).sin(), (i as f32).cos(), 0.5]))
+            .collect();
+        assert_eq!(kmeans(&vectors, 4), kmeans(&vectors, 4));
+    }
+
+    #[test]
+    fn renumbers_without_gaps() {
+        // 같은 벡터뿐이라 seed가 겹쳐도 번호는 0부터 연속
+        let vectors = vec![vec![1.0, 0.0]; 4];
+        let assignment = kmeans(&vectors, 3);
+        assert_eq!(assignment, vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn handles_empty_input() {
+        assert!(kmeans(&[], 3).is_empty());
+        assert!(kmeans(&[vec![1.0]], 0).is_empty());
+    }
+
+    #[test]
+    fn labels_with_distinctive_terms() {
+        let documents = vec![
+            counts("rust borrow note"),
+            counts("rust lifetime note"),
+            counts("garden tomato note"),
+        ];
+        let labels = label_clusters(&documents, &[0, 0, 1]);
+        assert_eq!(labels[&0][0], "rust");
+        assert_eq!(labels[&1][0], "garden");
+        assert!(labels[&1].iter().all(|term| term != "rust"));
+    }
+}
@@ -0,0 +1,748 @@
+// src/posts/dirty.rs
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use once_cell::sync::{Lazy, OnceCell};
+use redis::AsyncCommands;
+use serde::Serialize;
+use sqlx::{Postgres, pool::Pool};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
+
+use super::cache::{cache_key, flush_delete, flush_update};
+use super::models::{Post, PostResponse};
+use super::related::PENDING_DELETES_KEY;
+use super::utils::SharedConnection;
+use crate::config::FlushConfig;
+use crate::monitoring::record_cache_flush;
+
+// write-back cache가 아직 DB에 반영하지 않은 post (post id -> 캐시 payload)
+const DIRTY_KEY: &str = "posts:dirty";
+// post id -> 처음 dirty가 된 시각 / 마지막 write 시각 (unix ms)
+const DIRTY_SINCE_KEY: &str = "posts:dirty:since";
+const DIRTY_TOUCHED_KEY: &str = "posts:dirty:touched";
+// 한 번에 한 instance만 flush (값은 lock을 잡은 쪽의 token)
+const FLUSH_LOCK_KEY: &str = "posts:dirty:lock";
+// lock 대기 중 재시도 간격 / 작업 시간보다 lock을 더 유지하는 여유
+const LOCK_RETRY: Duration = Duration::from_millis(200);
+const LOCK_MARGIN: Duration = Duration::from_secs(5);
+
+// 지금 저장된 payload가 flush한 값과 같을 때만 삭제 (그 사이 새 write가 있으면 유지)
+const CLEAR_SCRIPT: &str = r#"
+if redis.call('HGET', KEYS[1], ARGV[1]) == ARGV[2] then
+    redis.call('HDEL', KEYS[2], ARGV[1])
+    redis.call('HDEL', KEYS[3], ARGV[1])
+    return redis.call('HDEL', KEYS[1], ARGV[1])
+end
+return 0
+"#;
+
+// 자기 token일 때만 lock 해제 (만료 후 다른 instance가 잡은 lock은 유지)
+const UNLOCK_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+enum Command {
+    Mark(i64, String),
+    Clear(i64, String),
+    Sync(oneshot::Sender<()>),
+}
+
+static TRACKER: OnceCell<mpsc::UnboundedSender<Command>> = OnceCell::new();
+static READER: OnceCell<SharedConnection> = OnceCell::new();
+// breaker 우회 중 DB에 직접 썼지만 Redis 사본을 아직 못 지운 post
+static STALE: Lazy<Mutex<HashSet<i64>>> = Lazy::new(Default::default);
+
+/// Starts the task that mirrors absorbed writes into the `posts:dirty` hash.
+///
+/// The hash outlives this process, so a shutdown (or the next instance after a
+/// crash) can flush whatever the write-back cache had not written yet.
+pub fn init(client: redis::Client) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    if TRACKER.set(tx).is_err() {
+        return;
+    }
+    let _ = READER.set(SharedConnection::new(client.clone()));
+
+    tokio::spawn(async move {
+        let mut conn = None;
+        while let Some(command) = rx.recv().await {
+            let (id, payload, mark) = match command {
+                Command::Mark(id, payload) => (id, payload, true),
+                Command::Clear(id, payload) => (id, payload, false),
+                Command::Sync(done) => {
+                    let _ = done.send(());
+                    continue;
+                }
+            };
+
+            if conn.is_none() {
+                conn = client.get_multiplexed_async_connection().await.ok();
+            }
+            let Some(redis) = conn.as_mut() else {
+                warn!(post_id = id, "Dirty tracker: Redis unavailable");
+                continue;
+            };
+
+            let result = if mark {
+                let now = Utc::now().timestamp_millis();
+                redis::pipe()
+                    .atomic()
+                    .hset(DIRTY_KEY, id, payload)
+                    .hset_nx(DIRTY_SINCE_KEY, id, now)
+                    .hset(DIRTY_TOUCHED_KEY, id, now)
+                    .query_async::<()>(redis)
+                    .await
+            } else {
+                clear(redis, id, &payload).await
+            };
+            if let Err(e) = result {
+                warn!(post_id = id, error = %e, "Dirty tracker: Redis command failed");
+                conn = None;
+            }
+        }
+    });
+}
+
+/// Records a post write absorbed by the cache (called from `write_to_cache`).
+pub fn mark(id: i64, payload: &str) {
+    send(Command::Mark(id, payload.to_string()));
+}
+
+/// Forgets a write once the flush callback stored `payload` in Postgres.
+pub fn mark_flushed(id: i64, payload: &str) {
+    send(Command::Clear(id, payload.to_string()));
+}
+
+fn send(command: Command) {
+    if let Some(tracker) = TRACKER.get() {
+        let _ = tracker.send(command);
+    }
+}
+
+/// Waits until every write recorded so far has reached Redis.
+async fn sync() {
+    if let Some(tracker) = TRACKER.get() {
+        let (done, wait) = oneshot::channel();
+        if tracker.send(Command::Sync(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+async fn clear(
+    conn: &mut redis::aio::MultiplexedConnection,
+    id: i64,
+    payload: &str,
+) -> redis::RedisResult<()> {
+    redis::Script::new(CLEAR_SCRIPT)
+        .key(DIRTY_KEY)
+        .key(DIRTY_SINCE_KEY)
+        .key(DIRTY_TOUCHED_KEY)
+        .arg(id)
+        .arg(payload)
+        .invoke_async::<i64>(conn)
+        .await
+        .map(|_| ())
+}
+
+/// Drops the dirty entry and cached copy of a post written straight to
+/// Postgres while the cache was bypassed, so neither a flush nor a cache hit
+/// brings the older version back.
+///
+/// When Redis cannot be reached the post is queued; [`is_stale`] stays true
+/// until [`retry_discards`] gets through.
+pub async fn discard(client: &redis::Client, id: i64) {
+    STALE.lock().unwrap().insert(id);
+    sync().await;
+    if let Err(e) = retry_discards(client).await {
+        warn!(post_id = id, error = %e, "Stale cache entry queued until Redis is back");
+    }
+}
+
+/// Whether `payload` is waiting in `posts:dirty`, i.e. the scheduled flusher
+/// will write it.
+pub async fn is_tracked(payload: &str) -> redis::RedisResult<bool> {
+    let Ok(post) = serde_json::from_str::<PostResponse>(payload) else {
+        return Ok(false);
+    };
+    let Some(reader) = READER.get() else {
+        return Ok(false);
+    };
+    sync().await;
+    let mut conn = reader.get().await?;
+    let stored: Option<String> = conn.hget(DIRTY_KEY, post.id).await?;
+    // 더 최신 write가 있어도 그 write가 같은 post를 flush
+    Ok(stored.is_some())
+}
+
+/// Whether a post still has a cached copy older than Postgres.
+pub fn is_stale(id: i64) -> bool {
+    STALE.lock().unwrap().contains(&id)
+}
+
+/// Drops the Redis copies of every queued post.
+pub async fn retry_discards(client: &redis::Client) -> redis::RedisResult<()> {
+    if STALE.lock().unwrap().is_empty() {
+        return Ok(());
+    }
+    let mut conn = client.get_multiplexed_async_connection().await?;
+    discard_queued(&mut conn).await
+}
+
+async fn discard_queued(conn: &mut redis::aio::MultiplexedConnection) -> redis::RedisResult<()> {
+    let ids: Vec<i64> = STALE.lock().unwrap().iter().copied().collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let keys: Vec<String> = ids.iter().map(|id| cache_key(*id)).collect();
+    redis::pipe()
+        .atomic()
+        .hdel(DIRTY_KEY, &ids)
+        .hdel(DIRTY_SINCE_KEY, &ids)
+        .hdel(DIRTY_TOUCHED_KEY, &ids)
+        .del(keys)
+        .query_async::<()>(conn)
+        .await?;
+    let mut stale = STALE.lock().unwrap();
+    for id in ids {
+        stale.remove(&id);
+    }
+    Ok(())
+}
+
+/// Applies unflushed title/content edits to posts read straight from Postgres
+/// and hides posts whose delete is still waiting in the cache.
+///
+/// Lets `GET /posts` and search agree with `GET /posts/:id` before the
+/// write-back flush runs. Search ranking still uses the stored embedding.
+pub async fn overlay(posts: &mut Vec<Post>) {
+    let Some(reader) = READER.get() else {
+        return;
+    };
+    if posts.is_empty() {
+        return;
+    }
+    let ids: Vec<i64> = posts.iter().map(|post| post.id).collect();
+    // 방금 응답한 write의 mark가 아직 channel에 있을 수 있음
+    sync().await;
+
+    let result = async {
+        let mut conn = reader.get().await?;
+        let pending: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(DIRTY_KEY)
+            .arg(&ids)
+            .query_async(&mut conn)
+            .await?;
+        let deleted: HashSet<i64> = conn.smembers(PENDING_DELETES_KEY).await?;
+        Ok::<_, redis::RedisError>((pending, deleted))
+    }
+    .await;
+    let (pending, deleted) = match result {
+        Ok(found) => found,
+        Err(e) => {
+            // overlay 없이 DB 내용 그대로 반환
+            warn!(error = %e, "Failed to read dirty posts for overlay");
+            reader.reset().await;
+            return;
+        }
+    };
+
+    apply_overlay(posts, pending, &deleted);
+}
+
+// pending은 posts와 같은 순서의 캐시 payload
+fn apply_overlay(posts: &mut Vec<Post>, pending: Vec<Option<String>>, deleted: &HashSet<i64>) {
+    for (post, raw) in posts.iter_mut().zip(pending) {
+        let Some(cached) = raw.and_then(|raw| serde_json::from_str::<PostResponse>(&raw).ok())
+        else {
+            continue;
+        };
+        if cached.user_id == post.user_id {
+            post.title = cached.title;
+            post.content = cached.content;
+            post.updated_at = cached.updated_at;
+        }
+    }
+    posts.retain(|post| !deleted.contains(&post.id));
+}
+
+/// Redis lock held during a flush pass, so replicas never write the same
+/// entries at the same time. It expires on its own if the holder dies.
+struct FlushLock {
+    token: String,
+}
+
+impl FlushLock {
+    async fn try_acquire(
+        conn: &mut redis::aio::MultiplexedConnection,
+        ttl: Duration,
+    ) -> redis::RedisResult<Option<Self>> {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(FLUSH_LOCK_KEY)
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(conn)
+            .await?;
+        Ok(acquired.map(|_| Self { token }))
+    }
+
+    /// Waits for the lock until `deadline`; it is held until `deadline` plus a
+    /// margin at most.
+    async fn acquire(
+        conn: &mut redis::aio::MultiplexedConnection,
+        deadline: Instant,
+    ) -> redis::RedisResult<Option<Self>> {
+        loop {
+            let ttl = deadline.saturating_duration_since(Instant::now()) + LOCK_MARGIN;
+            if let Some(lock) = Self::try_acquire(conn, ttl).await? {
+                return Ok(Some(lock));
+            }
+            if Instant::now() + LOCK_RETRY >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(LOCK_RETRY).await;
+        }
+    }
+
+    async fn release(self, conn: &mut redis::aio::MultiplexedConnection) {
+        let result = redis::Script::new(UNLOCK_SCRIPT)
+            .key(FLUSH_LOCK_KEY)
+            .arg(&self.token)
+            .invoke_async::<i64>(conn)
+            .await;
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to release flush lock; it expires on its own");
+        }
+    }
+}
+
+/// Outcome of a flush pass over `posts:dirty`.
+#[derive(Debug, Default, Serialize)]
+pub struct FlushReport {
+    pub flushed: usize,
+    /// Absorbed deletes applied to Postgres.
+    pub deleted: usize,
+    pub failed: Vec<i64>,
+    /// Entries not attempted because the deadline ran out.
+    pub skipped: Vec<i64>,
+}
+
+impl FlushReport {
+    pub fn log(&self, phase: &str) {
+        if self.failed.is_empty() && self.skipped.is_empty() {
+            info!(
+                phase,
+                flushed = self.flushed,
+                deleted = self.deleted,
+                "Dirty posts flushed"
+            );
+        } else {
+            error!(
+                phase,
+                flushed = self.flushed,
+                deleted = self.deleted,
+                failed = ?self.failed,
+                skipped = ?self.skipped,
+                "Some dirty posts could not be flushed; they stay in {DIRTY_KEY}"
+            );
+        }
+    }
+}
+
+/// Writes every post left in `posts:dirty` and every absorbed delete to
+/// Postgres, giving up at `timeout`.
+///
+/// Used at shutdown after the cache manager stopped, at startup to recover
+/// writes from an instance that died before flushing, and by the admin API.
+/// Runs under the flush lock, so replicas shutting down together take turns
+/// instead of writing the same posts twice.
+pub async fn flush_all(
+    db: &Pool<Postgres>,
+    client: &redis::Client,
+    timeout: Duration,
+) -> FlushReport {
+    let deadline = Instant::now() + timeout;
+    sync().await;
+
+    let mut conn = match client.get_multiplexed_async_connection().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!(error = %e, "Cannot read dirty posts: Redis unavailable");
+            return FlushReport::default();
+        }
+    };
+    let lock = match FlushLock::acquire(&mut conn, deadline).await {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            // 다른 instance가 flush 중이며, 남은 entry는 다음 flush가 처리
+            warn!("Flush lock still held by another instance, leaving dirty posts to it");
+            return FlushReport::default();
+        }
+        Err(e) => {
+            error!(error = %e, "Cannot take flush lock");
+            return FlushReport::default();
+        }
+    };
+
+    let pending = async {
+        discard_queued(&mut conn).await?;
+        let entries: Vec<(i64, String)> = conn.hgetall(DIRTY_KEY).await?;
+        let deletes: Vec<i64> = conn.smembers(PENDING_DELETES_KEY).await?;
+        Ok::<_, redis::RedisError>((entries, deletes))
+    }
+    .await;
+    let report = match pending {
+        Ok((entries, deletes)) => {
+            let mut report = flush_entries(db, &mut conn, entries, deadline).await;
+            flush_deletes(db, deletes, deadline, &mut report).await;
+            report
+        }
+        Err(e) => {
+            error!(error = %e, "Cannot read dirty posts");
+            FlushReport::default()
+        }
+    };
+    lock.release(&mut conn).await;
+    report
+}
+
+// related::forget가 성공한 delete를 pending 목록에서 지움
+async fn flush_deletes(
+    db: &Pool<Postgres>,
+    deletes: Vec<i64>,
+    deadline: Instant,
+    report: &mut FlushReport,
+) {
+    for id in deletes {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            report.skipped.push(id);
+            continue;
+        };
+        match tokio::time::timeout(remaining, flush_delete(db, id)).await {
+            Ok(Ok(_)) => report.deleted += 1,
+            Ok(Err(e)) => {
+                error!(post_id = id, error = %e, "Pending delete flush failed");
+                report.failed.push(id);
+            }
+            Err(_) => report.skipped.push(id),
+        }
+    }
+}
+
+/// Flushes a single post now. `None` when it has no pending writes.
+pub async fn flush_one(
+    db: &Pool<Postgres>,
+    client: &redis::Client,
+    id: i64,
+    timeout: Duration,
+) -> redis::RedisResult<Option<FlushReport>> {
+    let deadline = Instant::now() + timeout;
+    sync().await;
+
+    let mut conn = client.get_multiplexed_async_connection().await?;
+    let Some(lock) = FlushLock::acquire(&mut conn, deadline).await? else {
+        // 다른 instance의 flush가 끝나지 않음
+        return Ok(Some(FlushReport {
+            skipped: vec![id],
+            ..Default::default()
+        }));
+    };
+    let result = async {
+        discard_queued(&mut conn).await?;
+        let payload: Option<String> = conn.hget(DIRTY_KEY, id).await?;
+        Ok::<_, redis::RedisError>(payload)
+    }
+    .await;
+    let report = match result {
+        Ok(Some(payload)) => Ok(Some(
+            flush_entries(db, &mut conn, vec![(id, payload)], deadline).await,
+        )),
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    lock.release(&mut conn).await;
+    report
+}
+
+async fn flush_entries(
+    db: &Pool<Postgres>,
+    conn: &mut redis::aio::MultiplexedConnection,
+    entries: Vec<(i64, String)>,
+    deadline: Instant,
+) -> FlushReport {
+    let mut report = FlushReport::default();
+    for (id, payload) in entries {
+        // DB에 더 최신 내용이 있음; discard가 Redis에서 지울 때까지 건드리지 않음
+        if is_stale(id) {
+            report.skipped.push(id);
+            continue;
+        }
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            report.skipped.push(id);
+            continue;
+        };
+        let result = tokio::time::timeout(remaining, flush_update(db, &payload)).await;
+        if let Ok(flushed) = &result {
+            record_cache_flush("update", flushed.is_ok());
+        }
+        match result {
+            Ok(Ok(_)) => {
+                if let Err(e) = clear(conn, id, &payload).await {
+                    warn!(post_id = id, error = %e, "Flushed post but could not clear dirty entry");
+                }
+                report.flushed += 1;
+            }
+            Ok(Err((_, e))) => {
+                error!(post_id = id, error = %e, "Dirty post flush failed");
+                report.failed.push(id);
+            }
+            Err(_) => report.skipped.push(id),
+        }
+    }
+    report
+}
+
+/// Flushes dirty posts on our own schedule. The write-back cache's own flush
+/// hook defers to this, so it is the only path writing tracked posts.
+///
+/// Every `interval_secs` a post is written once its autosave burst settled
+/// (no write for `debounce_ms`), or regardless once it has been dirty for
+/// `max_dirty_age_secs`. Beyond `max_dirty_entries` the oldest are written
+/// right away. Replicas skip the tick while another one holds the flush lock.
+pub fn spawn_flusher(db: Pool<Postgres>, client: redis::Client, config: &'static FlushConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = flush_due(&db, &client, config).await {
+                warn!(error = %e, "Scheduled flush skipped: Redis unavailable");
+            }
+        }
+    });
+}
+
+async fn flush_due(
+    db: &Pool<Postgres>,
+    client: &redis::Client,
+    config: &FlushConfig,
+) -> redis::RedisResult<()> {
+    sync().await;
+    let mut conn = client.get_multiplexed_async_connection().await?;
+    let Some(lock) = FlushLock::try_acquire(&mut conn, config.interval() + LOCK_MARGIN).await?
+    else {
+        // 이번 주기는 lock을 잡은 instance가 처리
+        return Ok(());
+    };
+    let result = flush_due_locked(db, &mut conn, config).await;
+    lock.release(&mut conn).await;
+    result
+}
+
+async fn flush_due_locked(
+    db: &Pool<Postgres>,
+    conn: &mut redis::aio::MultiplexedConnection,
+    config: &FlushConfig,
+) -> redis::RedisResult<()> {
+    discard_queued(conn).await?;
+    let entries: HashMap<i64, String> = conn.hgetall(DIRTY_KEY).await?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let since: HashMap<i64, i64> = conn.hgetall(DIRTY_SINCE_KEY).await?;
+    let touched: HashMap<i64, i64> = conn.hgetall(DIRTY_TOUCHED_KEY).await?;
+
+    let now = Utc::now().timestamp_millis();
+    let due = select_due(entries, &since, &touched, now, config);
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let report = flush_entries(db, conn, due, Instant::now() + config.interval()).await;
+    if !report.failed.is_empty() || !report.skipped.is_empty() {
+        report.log("scheduled");
+    }
+    Ok(())
+}
+
+// 이번 주기에 flush할 entry를 오래된 순서로 고름 (시각은 unix ms)
+fn select_due(
+    entries: HashMap<i64, String>,
+    since: &HashMap<i64, i64>,
+    touched: &HashMap<i64, i64>,
+    now: i64,
+    config: &FlushConfig,
+) -> Vec<(i64, String)> {
+    let max_age = config.max_dirty_age_secs as i64 * 1000;
+    let debounce = config.debounce_ms as i64;
+
+    let mut entries: Vec<(i64, i64, String)> = entries
+        .into_iter()
+        .map(|(id, payload)| (since.get(&id).copied().unwrap_or(0), id, payload))
+        .collect();
+    entries.sort_by_key(|(since, id, _)| (*since, *id));
+    let overflow = entries.len().saturating_sub(config.max_dirty_entries);
+
+    entries
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (since, id, _))| {
+            let settled = now - touched.get(id).copied().unwrap_or(0) >= debounce;
+            *i < overflow || settled || now - since >= max_age
+        })
+        .map(|(_, (_, id, payload))| (id, payload))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration as ChronoDuration, Utc};
+
+    use super::*;
+
+    fn post(id: i64, user_id: i64, title: &str) -> Post {
+        Post {
+            id,
+            title: title.to_string(),
+            content: "stored".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now() - ChronoDuration::hours(1),
+            embedding: None,
+            user_id,
+        }
+    }
+
+    fn cached(id: i64, user_id: i64, title: &str) -> Option<String> {
+        let post = post(id, user_id, title);
+        Some(
+            serde_json::to_string(&PostResponse {
+                id,
+                title: title.to_string(),
+                content: "edited".to_string(),
+                created_at: post.created_at,
+                updated_at: Utc::now(),
+                user_id,
+                related_posts: Vec::new(),
+            })
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn overlays_unflushed_edits() {
+        let mut posts = vec![post(1, 7, "old"), post(2, 7, "untouched")];
+        apply_overlay(&mut posts, vec![cached(1, 7, "new"), None], &HashSet::new());
+        assert_eq!(posts[0].title, "new");
+        assert_eq!(posts[0].content, "edited");
+        assert!(posts[0].updated_at > posts[1].updated_at);
+        assert_eq!(posts[1].title, "untouched");
+    }
+
+    #[test]
+    fn ignores_payloads_of_another_user_and_garbage() {
+        let mut posts = vec![post(1, 7, "old"), post(2, 7, "old")];
+        apply_overlay(
+            &mut posts,
+            vec![cached(1, 8, "hijack"), Some("not json".to_string())],
+            &HashSet::new(),
+        );
+        assert!(posts.iter().all(|post| post.title == "old"));
+    }
+
+    fn flush_config() -> FlushConfig {
+        FlushConfig {
+            interval_secs: 5,
+            max_dirty_age_secs: 60,
+            max_dirty_entries: 100,
+            debounce_ms: 2_000,
+        }
+    }
+
+    fn due_ids(
+        ids: &[i64],
+        since: &[(i64, i64)],
+        touched: &[(i64, i64)],
+        now: i64,
+        config: &FlushConfig,
+    ) -> Vec<i64> {
+        let entries = ids
+            .iter()
+            .map(|id| (*id, format!("payload {id}")))
+            .collect();
+        let since = since.iter().copied().collect();
+        let touched = touched.iter().copied().collect();
+        select_due(entries, &since, &touched, now, config)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn waits_for_autosave_bursts_to_settle() {
+        let now = 100_000;
+        let due = due_ids(
+            &[1, 2],
+            &[(1, now - 10_000), (2, now - 10_000)],
+            &[(1, now - 500), (2, now - 2_000)],
+            now,
+            &flush_config(),
+        );
+        assert_eq!(due, vec![2]);
+    }
+
+    #[test]
+    fn flushes_old_entries_during_a_burst() {
+        let now = 100_000;
+        let due = due_ids(
+            &[1, 2],
+            &[(1, now - 60_000), (2, now - 59_999)],
+            &[(1, now), (2, now)],
+            now,
+            &flush_config(),
+        );
+        assert_eq!(due, vec![1]);
+    }
+
+    #[test]
+    fn flushes_oldest_above_max_entries() {
+        let now = 100_000;
+        let config = FlushConfig {
+            max_dirty_entries: 1,
+            ..flush_config()
+        };
+        let due = due_ids(
+            &[1, 2, 3],
+            &[(1, now - 300), (2, now - 100), (3, now - 200)],
+            &[(1, now), (2, now), (3, now)],
+            now,
+            &config,
+        );
+        assert_eq!(due, vec![1, 3]);
+    }
+
+    #[test]
+    fn entries_without_timestamps_are_due() {
+        let due = due_ids(&[4], &[], &[], 100_000, &flush_config());
+        assert_eq!(due, vec![4]);
+    }
+
+    #[test]
+    fn hides_pending_deletes() {
+        let mut posts = vec![post(1, 7, "a"), post(2, 7, "b"), post(3, 7, "c")];
+        apply_overlay(&mut posts, vec![None, None, None], &HashSet::from([2]));
+        let ids: Vec<i64> = posts.iter().map(|post| post.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+    }
+}
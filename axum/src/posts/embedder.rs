@@ -0,0 +1,532 @@
+// src/posts/embedder.rs
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::{collections::HashMap, time::Duration};
+
+use metrics::{gauge, histogram};
+use once_cell::sync::OnceCell;
+use tokio::sync::{Semaphore, mpsc, oneshot};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use super::models::{
+    BatchEmbeddingRequest, BatchEmbeddingResponse, EmbeddingResponse, QueryRequest,
+};
+use super::query_cache;
+use super::utils::{EmbeddingApiError, http_client, post_with_retry};
+use crate::config::EmbeddingConfig;
+use crate::telemetry::{current_request_id, with_request_id};
+
+// 백그라운드 health probe 하나의 제한 시간
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+// 대기열 크기 (batch 몇 개 분량). 가득 차면 embed()가 기다림
+const QUEUE_BATCHES: usize = 8;
+// 동시에 보내는 batch 수. 모두 응답을 기다리는 동안에는 대기열에서 더 꺼내지 않음
+const IN_FLIGHT_BATCHES: usize = 4;
+
+static EMBEDDER: OnceCell<Embedder> = OnceCell::new();
+
+struct Job {
+    text: String,
+    model: Option<String>,
+    // batch task는 요청의 task-local 밖에서 실행되므로 함께 전달
+    request_id: Option<String>,
+    reply: oneshot::Sender<Result<EmbeddingResponse, EmbeddingApiError>>,
+}
+
+/// Document embedding client that coalesces concurrent requests.
+///
+/// Texts submitted within `batch_window_ms` of each other (up to
+/// `max_batch_size`) go to `/embed` as one `texts` list per model, over the
+/// shared pooled HTTP client. A batch the API rejects is retried one text at a
+/// time, so a single bad text only fails its own request. At most
+/// `IN_FLIGHT_BATCHES` batches are sent at once.
+#[derive(Clone)]
+pub struct Embedder {
+    jobs: mpsc::Sender<Job>,
+    client: reqwest::Client,
+    config: &'static EmbeddingConfig,
+    query_url: String,
+    healthy: Arc<AtomicBool>,
+}
+
+/// Starts the batching task and the health monitor; also kept globally for the
+/// cache callbacks and background workers that have no app state.
+pub fn init(config: &'static EmbeddingConfig) -> Embedder {
+    EMBEDDER
+        .get_or_init(|| {
+            let (tx, rx) = mpsc::channel(config.max_batch_size.max(1) * QUEUE_BATCHES);
+            let client = http_client(config);
+            let healthy = Arc::new(AtomicBool::new(true));
+            tokio::spawn(monitor(
+                client.clone(),
+                format!("{}/health", config.api_url),
+                config.api_key.clone(),
+                Duration::from_secs(config.health_interval_secs),
+                healthy.clone(),
+            ));
+            tokio::spawn(run(
+                rx,
+                client.clone(),
+                config,
+                format!("{}/embed", config.api_url),
+                Duration::from_millis(config.batch_window_ms),
+                config.max_batch_size,
+                healthy.clone(),
+            ));
+            Embedder {
+                jobs: tx,
+                client,
+                config,
+                query_url: format!("{}/query-embedding", config.api_url),
+                healthy,
+            }
+        })
+        .clone()
+}
+
+/// The embedder started by [`init`].
+pub fn get() -> Embedder {
+    EMBEDDER
+        .get()
+        .expect("embedder::init must run before embedder::get")
+        .clone()
+}
+
+impl Embedder {
+    /// Embeds one document text with `model` (the API default when `None`).
+    ///
+    /// Waits while the queue is full, so bulk work such as re-embedding slows
+    /// down instead of queueing without bound.
+    pub async fn embed(
+        &self,
+        text: String,
+        model: Option<String>,
+    ) -> Result<EmbeddingResponse, EmbeddingApiError> {
+        let (reply, answer) = oneshot::channel();
+        self.jobs
+            .send(Job {
+                text,
+                model,
+                request_id: current_request_id(),
+                reply,
+            })
+            .await
+            .map_err(|_| EmbeddingApiError::Batch("embedder stopped".to_string()))?;
+        answer
+            .await
+            .map_err(|_| EmbeddingApiError::Batch("embedder dropped the request".to_string()))?
+    }
+
+    /// `embedding.model`, the model used until a switch has completed.
+    pub fn default_model(&self) -> &'static str {
+        &self.config.model
+    }
+
+    /// Whether the last background probe of `/health` succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// Embeds a search query with `model`, from the query cache when possible.
+    ///
+    /// Misses are not batched, since a user is waiting on them, and fail fast
+    /// with [`EmbeddingApiError::Unhealthy`] while the service is down. An
+    /// answer from another model, or not of `dimension` when given, is an
+    /// [`EmbeddingApiError::Mismatch`] and is not cached.
+    pub async fn embed_query(
+        &self,
+        query: String,
+        model: String,
+        dimension: Option<i32>,
+    ) -> Result<Vec<f32>, EmbeddingApiError> {
+        if let Some(embedding) = query_cache::get(&model, &query).await {
+            return Ok(embedding);
+        }
+        if !self.is_healthy() {
+            return Err(EmbeddingApiError::Unhealthy);
+        }
+        let request = QueryRequest {
+            query: query.clone(),
+            model: Some(model.clone()),
+        };
+        let response: EmbeddingResponse =
+            post_with_retry(&self.client, self.config, &self.query_url, request).await?;
+        check_query_embedding(&response, &model, dimension)?;
+        query_cache::store(&model, &query, &response.embedding).await;
+        Ok(response.embedding)
+    }
+}
+
+// 저장된 embedding과 비교할 수 없는 query embedding은 cache에 남기지 않음
+fn check_query_embedding(
+    response: &EmbeddingResponse,
+    model: &str,
+    dimension: Option<i32>,
+) -> Result<(), EmbeddingApiError> {
+    if let Some(answered) = response.model.as_deref().filter(|m| *m != model) {
+        return Err(EmbeddingApiError::Mismatch(format!(
+            "asked for model {model}, got {answered}"
+        )));
+    }
+    match dimension {
+        Some(dimension) if response.embedding.len() as i32 != dimension => {
+            Err(EmbeddingApiError::Mismatch(format!(
+                "expected {dimension} dimensions, got {}",
+                response.embedding.len()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Probes `/health` every `interval` so requests can check a flag instead of
+/// calling the service themselves.
+async fn monitor(
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    interval: Duration,
+    healthy: Arc<AtomicBool>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let result = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .timeout(HEALTH_TIMEOUT)
+            .send()
+            .await;
+        let up = matches!(&result, Ok(response) if response.status().is_success());
+        gauge!("embedding_api_up").set(if up { 1.0 } else { 0.0 });
+
+        let was_up = healthy.swap(up, Ordering::Relaxed);
+        match (was_up, up) {
+            (true, false) => match result {
+                Ok(response) => {
+                    warn!(status = %response.status(), "Embedding API became unhealthy")
+                }
+                Err(e) => warn!(error = %e, "Embedding API became unreachable"),
+            },
+            (false, true) => info!("Embedding API recovered"),
+            _ => {}
+        }
+    }
+}
+
+async fn run(
+    mut rx: mpsc::Receiver<Job>,
+    client: reqwest::Client,
+    config: &'static EmbeddingConfig,
+    url: String,
+    window: Duration,
+    max: usize,
+    healthy: Arc<AtomicBool>,
+) {
+    let in_flight = Arc::new(Semaphore::new(IN_FLIGHT_BATCHES));
+    while let Some(first) = rx.recv().await {
+        let jobs = collect_batch(&mut rx, first, window, max).await;
+        for (model, jobs) in group_by_model(jobs) {
+            // spawn 전에 기다려서 API가 느리면 대기열이 차고 embed()가 기다리게 함
+            let permit = in_flight
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("in-flight semaphore is never closed");
+            let batch = send_batch(
+                client.clone(),
+                config,
+                url.clone(),
+                model,
+                jobs,
+                healthy.clone(),
+            );
+            tokio::spawn(async move {
+                batch.await;
+                drop(permit);
+            });
+        }
+    }
+}
+
+/// Waits up to `window` after `first` for more items, stopping at `max`.
+async fn collect_batch<T>(
+    rx: &mut mpsc::Receiver<T>,
+    first: T,
+    window: Duration,
+    max: usize,
+) -> Vec<T> {
+    let mut items = vec![first];
+    let deadline = tokio::time::sleep(window);
+    tokio::pin!(deadline);
+    while items.len() < max {
+        tokio::select! {
+            item = rx.recv() => match item {
+                Some(item) => items.push(item),
+                None => break,
+            },
+            _ = &mut deadline => break,
+        }
+    }
+    items
+}
+
+// 요청 하나에는 한 model만
+fn group_by_model(jobs: Vec<Job>) -> HashMap<Option<String>, Vec<Job>> {
+    let mut by_model: HashMap<Option<String>, Vec<Job>> = HashMap::new();
+    for job in jobs {
+        by_model.entry(job.model.clone()).or_default().push(job);
+    }
+    by_model
+}
+
+async fn send_batch(
+    client: reqwest::Client,
+    config: &EmbeddingConfig,
+    url: String,
+    model: Option<String>,
+    jobs: Vec<Job>,
+    healthy: Arc<AtomicBool>,
+) {
+    histogram!("embedding_batch_size").record(jobs.len() as f64);
+    let request_id = batch_request_id(&jobs);
+    let (texts, jobs): (Vec<String>, Vec<_>) = jobs
+        .into_iter()
+        .map(|job| (job.text, (job.request_id, job.reply)))
+        .unzip();
+    let (request_ids, replies): (Vec<_>, Vec<_>) = jobs.into_iter().unzip();
+
+    let e = match with_request_id(
+        request_id,
+        embed_texts(&client, config, &url, &model, texts.clone()),
+    )
+    .await
+    {
+        Ok(embeddings) => {
+            for (embedding, reply) in embeddings.into_iter().zip(replies) {
+                let _ = reply.send(Ok(embedding));
+            }
+            return;
+        }
+        Err(e) => e.to_string(),
+    };
+    // 서비스가 내려간 경우가 아니면 어느 text가 문제인지 하나씩 다시 요청
+    if replies.len() == 1 || !healthy.load(Ordering::Relaxed) {
+        for reply in replies {
+            let _ = reply.send(Err(EmbeddingApiError::Batch(e.clone())));
+        }
+        return;
+    }
+    warn!(error = %e, texts = replies.len(), "Embedding batch failed, retrying texts one by one");
+    for ((text, request_id), reply) in texts.into_iter().zip(request_ids).zip(replies) {
+        let result = with_request_id(
+            request_id,
+            embed_texts(&client, config, &url, &model, vec![text]),
+        )
+        .await
+        .map(|mut embeddings| embeddings.remove(0))
+        .map_err(|e| EmbeddingApiError::Batch(e.to_string()));
+        let _ = reply.send(result);
+    }
+}
+
+// 여러 요청의 text를 묶은 batch에는 각 요청의 id를 모두 보냄
+fn batch_request_id(jobs: &[Job]) -> Option<String> {
+    let mut ids: Vec<&str> = Vec::new();
+    for id in jobs.iter().filter_map(|job| job.request_id.as_deref()) {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    (!ids.is_empty()).then(|| ids.join(","))
+}
+
+// texts와 같은 순서, 같은 개수의 embedding
+async fn embed_texts(
+    client: &reqwest::Client,
+    config: &EmbeddingConfig,
+    url: &str,
+    model: &Option<String>,
+    texts: Vec<String>,
+) -> Result<Vec<EmbeddingResponse>, EmbeddingApiError> {
+    let expected = texts.len();
+    let batch = post_with_retry::<_, BatchEmbeddingResponse>(
+        client,
+        config,
+        url,
+        BatchEmbeddingRequest {
+            texts,
+            model: model.clone(),
+        },
+    )
+    .await?;
+    if batch.embeddings.len() != expected {
+        let e = format!(
+            "expected {expected} embeddings, got {}",
+            batch.embeddings.len()
+        );
+        warn!(error = %e, "Embedding API returned a short batch");
+        return Err(EmbeddingApiError::Batch(e));
+    }
+    Ok(batch
+        .embeddings
+        .into_iter()
+        .map(|embedding| EmbeddingResponse {
+            embedding,
+            model: batch.model.clone(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    fn job(text: &str, model: Option<&str>) -> Job {
+        let (reply, _) = oneshot::channel();
+        Job {
+            text: text.to_string(),
+            model: model.map(str::to_string),
+            request_id: None,
+            reply,
+        }
+    }
+
+    fn job_from(request_id: &str) -> Job {
+        Job {
+            request_id: Some(request_id.to_string()),
+            ..job("text", None)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn collects_items_sent_within_the_window() {
+        let (tx, mut rx) = mpsc::channel(16);
+        tx.send(2).await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            tx.send(3).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            // window가 지난 뒤라 다음 batch
+            tx.send(4).await.unwrap();
+        });
+        let batch = collect_batch(&mut rx, 1, Duration::from_millis(10), 32).await;
+        assert_eq!(batch, vec![1, 2, 3]);
+        assert_eq!(rx.recv().await, Some(4));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_at_max_batch_size() {
+        let (tx, mut rx) = mpsc::channel(16);
+        for i in 2..=5 {
+            tx.send(i).await.unwrap();
+        }
+        let started = tokio::time::Instant::now();
+        let batch = collect_batch(&mut rx, 1, Duration::from_secs(60), 3).await;
+        assert_eq!(batch, vec![1, 2, 3]);
+        // 가득 차면 window를 기다리지 않음
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(rx.recv().await, Some(4));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn returns_early_when_senders_are_gone() {
+        let (tx, mut rx) = mpsc::channel(16);
+        tx.send(2).await.unwrap();
+        drop(tx);
+        let batch = collect_batch(&mut rx, 1, Duration::from_secs(60), 32).await;
+        assert_eq!(batch, vec![1, 2]);
+    }
+
+    #[test]
+    fn groups_jobs_by_model_in_order() {
+        let groups = group_by_model(vec![job("a", None), job("b", Some("m2")), job("c", None)]);
+        assert_eq!(groups.len(), 2);
+        let texts = |model: Option<&str>| -> Vec<String> {
+            groups[&model.map(str::to_string)]
+                .iter()
+                .map(|job| job.text.clone())
+                .collect()
+        };
+        assert_eq!(texts(None), vec!["a", "c"]);
+        assert_eq!(texts(Some("m2")), vec!["b"]);
+    }
+
+    #[test]
+    fn batch_carries_the_request_ids_of_its_texts() {
+        assert_eq!(batch_request_id(&[job("a", None)]), None);
+        let jobs = [
+            job_from("req-1"),
+            job("b", None),
+            job_from("req-2"),
+            job_from("req-1"),
+        ];
+        assert_eq!(batch_request_id(&jobs).as_deref(), Some("req-1,req-2"));
+    }
+
+    #[tokio::test]
+    async fn sends_the_request_id_from_the_spawned_batch_task() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embed"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "embeddings": [[0.5, 0.25]],
+                "model": "bge-m3",
+            })))
+            .mount(&server)
+            .await;
+        let config: &'static EmbeddingConfig = Box::leak(Box::new(EmbeddingConfig {
+            api_url: server.uri(),
+            ..EmbeddingConfig::default()
+        }));
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(run(
+            rx,
+            reqwest::Client::new(),
+            config,
+            format!("{}/embed", server.uri()),
+            Duration::from_millis(1),
+            1,
+            Arc::new(AtomicBool::new(true)),
+        ));
+
+        let (reply, answer) = oneshot::channel();
+        tx.send(Job {
+            reply,
+            ..job_from("req-7")
+        })
+        .await
+        .unwrap();
+        assert_eq!(answer.await.unwrap().unwrap().embedding, vec![0.5, 0.25]);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers.get("x-request-id").unwrap(), "req-7");
+    }
+
+    #[test]
+    fn checks_query_embeddings_before_caching() {
+        let response = |model: Option<&str>| EmbeddingResponse {
+            embedding: vec![0.0; 3],
+            model: model.map(str::to_string),
+        };
+        assert!(check_query_embedding(&response(Some("bge-m3")), "bge-m3", Some(3)).is_ok());
+        // model을 알려주지 않는 API, 아직 embedding이 없는 모델
+        assert!(check_query_embedding(&response(None), "bge-m3", Some(3)).is_ok());
+        assert!(check_query_embedding(&response(None), "bge-m3", None).is_ok());
+
+        let error = check_query_embedding(&response(Some("e5-large")), "bge-m3", Some(3));
+        assert!(matches!(error, Err(EmbeddingApiError::Mismatch(e)) if e.contains("e5-large")));
+        let error = check_query_embedding(&response(None), "bge-m3", Some(4));
+        assert!(matches!(error, Err(EmbeddingApiError::Mismatch(e)) if e.contains("expected 4")));
+    }
+}
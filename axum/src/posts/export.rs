@@ -0,0 +1,385 @@
+// src/posts/export.rs
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use super::models::{GraphData, GraphFormat, GraphNode};
+
+/// Renders the graph for Gephi / Graphviz / linked-data tools.
+///
+/// Links are stored per post (top-k neighbors), so A→B and B→A can both
+/// exist; exports merge them into one undirected edge with the higher weight.
+/// Posts carry no tags, so the cluster label stands in as the topic.
+pub fn render(format: GraphFormat, graph: &GraphData) -> Response {
+    let edges = undirected_edges(graph);
+    let (body, content_type, extension) = match format {
+        GraphFormat::Graphml => (graphml(graph, &edges), "application/graphml+xml", "graphml"),
+        GraphFormat::Gexf => (gexf(graph, &edges), "application/gexf+xml", "gexf"),
+        GraphFormat::Dot => (dot(graph, &edges), "text/vnd.graphviz", "dot"),
+        GraphFormat::JsonLd => (json_ld(graph, &edges), "application/ld+json", "jsonld"),
+        GraphFormat::Json => (
+            serde_json::to_string(graph).unwrap_or_default(),
+            "application/json",
+            "json",
+        ),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"graph.{extension}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+fn undirected_edges(graph: &GraphData) -> Vec<(&str, &str, f32)> {
+    let mut edges: BTreeMap<(&str, &str), f32> = BTreeMap::new();
+    for link in &graph.links {
+        let (a, b) = (link.source.as_str(), link.target.as_str());
+        let key = if a <= b { (a, b) } else { (b, a) };
+        let weight = edges.entry(key).or_insert(link.value);
+        *weight = weight.max(link.value);
+    }
+    edges
+        .into_iter()
+        .map(|((source, target), weight)| (source, target, weight))
+        .collect()
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn timestamp(node: &GraphNode, updated: bool) -> String {
+    let value = if updated {
+        node.updated_at
+    } else {
+        node.created_at
+    };
+    value.map(|at| at.to_rfc3339()).unwrap_or_default()
+}
+
+fn graphml(graph: &GraphData, edges: &[(&str, &str, f32)]) -> String {
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="created_at" for="node" attr.name="created_at" attr.type="string"/>
+  <key id="updated_at" for="node" attr.name="updated_at" attr.type="string"/>
+  <key id="cluster_id" for="node" attr.name="cluster_id" attr.type="int"/>
+  <key id="cluster_label" for="node" attr.name="cluster_label" attr.type="string"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="double"/>
+  <graph id="notes" edgedefault="undirected">
+"#,
+    );
+    for node in &graph.nodes {
+        let _ = writeln!(out, r#"    <node id="{}">"#, xml_escape(&node.id));
+        let _ = writeln!(
+            out,
+            r#"      <data key="label">{}</data>"#,
+            xml_escape(&node.name)
+        );
+        let _ = writeln!(
+            out,
+            r#"      <data key="created_at">{}</data>"#,
+            timestamp(node, false)
+        );
+        let _ = writeln!(
+            out,
+            r#"      <data key="updated_at">{}</data>"#,
+            timestamp(node, true)
+        );
+        if let Some(cluster_id) = node.cluster_id {
+            let _ = writeln!(out, r#"      <data key="cluster_id">{cluster_id}</data>"#);
+        }
+        if let Some(label) = &node.cluster_label {
+            let _ = writeln!(
+                out,
+                r#"      <data key="cluster_label">{}</data>"#,
+                xml_escape(label)
+            );
+        }
+        out.push_str("    </node>\n");
+    }
+    for (i, (source, target, weight)) in edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            r#"    <edge id="e{i}" source="{}" target="{}"><data key="weight">{weight}</data></edge>"#,
+            xml_escape(source),
+            xml_escape(target),
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn gexf(graph: &GraphData, edges: &[(&str, &str, f32)]) -> String {
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.3" version="1.3">
+  <graph defaultedgetype="undirected" mode="static">
+    <attributes class="node">
+      <attribute id="created_at" title="created_at" type="string"/>
+      <attribute id="updated_at" title="updated_at" type="string"/>
+      <attribute id="cluster_id" title="cluster_id" type="integer"/>
+      <attribute id="cluster_label" title="cluster_label" type="string"/>
+    </attributes>
+    <nodes>
+"#,
+    );
+    for node in &graph.nodes {
+        let _ = writeln!(
+            out,
+            r#"      <node id="{}" label="{}">"#,
+            xml_escape(&node.id),
+            xml_escape(&node.name)
+        );
+        out.push_str("        <attvalues>\n");
+        let _ = writeln!(
+            out,
+            r#"          <attvalue for="created_at" value="{}"/>"#,
+            timestamp(node, false)
+        );
+        let _ = writeln!(
+            out,
+            r#"          <attvalue for="updated_at" value="{}"/>"#,
+            timestamp(node, true)
+        );
+        if let Some(cluster_id) = node.cluster_id {
+            let _ = writeln!(
+                out,
+                r#"          <attvalue for="cluster_id" value="{cluster_id}"/>"#
+            );
+        }
+        if let Some(label) = &node.cluster_label {
+            let _ = writeln!(
+                out,
+                r#"          <attvalue for="cluster_label" value="{}"/>"#,
+                xml_escape(label)
+            );
+        }
+        out.push_str("        </attvalues>\n      </node>\n");
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for (i, (source, target, weight)) in edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            r#"      <edge id="e{i}" source="{}" target="{}" weight="{weight}"/>"#,
+            xml_escape(source),
+            xml_escape(target),
+        );
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    out
+}
+
+fn dot_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', " ")
+}
+
+fn dot(graph: &GraphData, edges: &[(&str, &str, f32)]) -> String {
+    let mut out = String::from("graph notes {\n");
+    for node in &graph.nodes {
+        let _ = write!(
+            out,
+            r#"  "{}" [label="{}", created_at="{}", updated_at="{}""#,
+            dot_escape(&node.id),
+            dot_escape(&node.name),
+            timestamp(node, false),
+            timestamp(node, true),
+        );
+        if let Some(cluster_id) = node.cluster_id {
+            let _ = write!(out, r#", cluster_id="{cluster_id}""#);
+        }
+        if let Some(label) = &node.cluster_label {
+            let _ = write!(out, r#", cluster_label="{}""#, dot_escape(label));
+        }
+        out.push_str("];\n");
+    }
+    for (source, target, weight) in edges {
+        let _ = writeln!(
+            out,
+            r#"  "{}" -- "{}" [weight={weight}];"#,
+            dot_escape(source),
+            dot_escape(target),
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn json_ld(graph: &GraphData, edges: &[(&str, &str, f32)]) -> String {
+    let mut items: Vec<serde_json::Value> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            json!({
+                "@id": format!("post:{}", node.id),
+                "@type": "CreativeWork",
+                "name": node.name,
+                "dateCreated": node.created_at,
+                "dateModified": node.updated_at,
+                "nn:clusterId": node.cluster_id,
+                "about": node.cluster_label,
+            })
+        })
+        .collect();
+    items.extend(edges.iter().map(|(source, target, weight)| {
+        json!({
+            "@type": "nn:Similarity",
+            "nn:source": { "@id": format!("post:{source}") },
+            "nn:target": { "@id": format!("post:{target}") },
+            "nn:weight": weight,
+        })
+    }));
+
+    let document = json!({
+        "@context": {
+            "@vocab": "https://schema.org/",
+            "nn": "urn:neural-notes:",
+            "post": "urn:neural-notes:post:",
+        },
+        "@graph": items,
+    });
+    serde_json::to_string_pretty(&document).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::posts::models::GraphLink;
+
+    fn node(id: &str, name: &str) -> GraphNode {
+        GraphNode {
+            id: id.to_string(),
+            name: name.to_string(),
+            created_at: Some(chrono::Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap()),
+            updated_at: None,
+            cluster_id: Some(1),
+            cluster_label: Some("rust, async".to_string()),
+        }
+    }
+
+    fn link(source: &str, target: &str, value: f32) -> GraphLink {
+        GraphLink {
+            source: source.to_string(),
+            target: target.to_string(),
+            value,
+        }
+    }
+
+    fn graph() -> GraphData {
+        GraphData {
+            nodes: vec![node("1", "Tom & \"Jerry\" <3"), node("2", "plain")],
+            links: vec![link("1", "2", 0.5), link("2", "1", 0.75)],
+        }
+    }
+
+    #[test]
+    fn merges_both_directions_with_the_higher_weight() {
+        let graph = GraphData {
+            nodes: Vec::new(),
+            links: vec![
+                link("2", "1", 0.5),
+                link("1", "2", 0.75),
+                link("3", "1", 0.25),
+            ],
+        };
+        assert_eq!(
+            undirected_edges(&graph),
+            vec![("1", "2", 0.75), ("1", "3", 0.25)]
+        );
+    }
+
+    #[test]
+    fn graphml_escapes_labels() {
+        let graph = graph();
+        let out = graphml(&graph, &undirected_edges(&graph));
+        assert!(out.contains(r#"<data key="label">Tom &amp; &quot;Jerry&quot; &lt;3</data>"#));
+        assert!(out.contains(r#"<data key="created_at">2025-01-02T03:04:05+00:00</data>"#));
+        assert!(out.contains(r#"<data key="updated_at"></data>"#));
+        assert!(out.contains(
+            r#"<edge id="e0" source="1" target="2"><data key="weight">0.75</data></edge>"#
+        ));
+        assert_eq!(out.matches("<edge ").count(), 1);
+        assert!(out.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn gexf_lists_nodes_and_weighted_edges() {
+        let graph = graph();
+        let out = gexf(&graph, &undirected_edges(&graph));
+        assert!(out.contains(r#"<node id="1" label="Tom &amp; &quot;Jerry&quot; &lt;3">"#));
+        assert!(out.contains(r#"<attvalue for="cluster_id" value="1"/>"#));
+        assert!(out.contains(r#"<attvalue for="cluster_label" value="rust, async"/>"#));
+        assert!(out.contains(r#"<edge id="e0" source="1" target="2" weight="0.75"/>"#));
+        assert!(out.ends_with("</gexf>\n"));
+    }
+
+    #[test]
+    fn dot_escapes_quotes_and_newlines() {
+        let graph = GraphData {
+            nodes: vec![node("1", "say \"hi\"\nback\\slash")],
+            links: vec![link("1", "2", 0.5)],
+        };
+        let out = dot(&graph, &undirected_edges(&graph));
+        assert!(out.starts_with("graph notes {\n"));
+        assert!(out.contains(r#"label="say \"hi\" back\\slash""#));
+        assert!(out.contains(r#"cluster_label="rust, async""#));
+        assert!(out.contains(r#"  "1" -- "2" [weight=0.5];"#));
+        assert!(out.ends_with("}\n"));
+    }
+
+    #[test]
+    fn json_ld_links_posts_by_id() {
+        let graph = graph();
+        let out = json_ld(&graph, &undirected_edges(&graph));
+        let document: serde_json::Value = serde_json::from_str(&out).unwrap();
+        let items = document["@graph"].as_array().unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0]["@id"], "post:1");
+        assert_eq!(items[0]["name"], "Tom & \"Jerry\" <3");
+        assert_eq!(items[0]["about"], "rust, async");
+        assert_eq!(items[2]["nn:source"]["@id"], "post:1");
+        assert_eq!(items[2]["nn:target"]["@id"], "post:2");
+        assert_eq!(items[2]["nn:weight"], 0.75);
+    }
+
+    #[test]
+    fn sets_content_type_and_filename() {
+        let response = render(GraphFormat::Dot, &graph());
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/vnd.graphviz"
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"graph.dot\""
+        );
+        let response = render(GraphFormat::JsonLd, &graph());
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/ld+json"
+        );
+    }
+}
@@ -1,102 +1,453 @@
+use std::collections::HashSet;
+
 use axum::{
-    extract::{Json, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
 use jwt_authorizer::JwtClaims;
-use pgvector::Vector;
-use sqlx::{Postgres, pool::Pool};
+use once_cell::sync::OnceCell;
+use redis::AsyncCommands;
+use sqlx::{PgConnection, Postgres, pool::Pool};
+use tracing::{error, warn};
 
-use super::models::{GraphData, GraphLink, GraphNode, Post, PostGraphData};
-use super::utils::internal_error;
+use super::cluster;
+use super::export;
+use super::models::{
+    EgoGraphQuery, GraphData, GraphFormat, GraphLink, GraphNode, GraphQuery, Post,
+};
+use super::utils::{SharedConnection, internal_error};
 use crate::auth::UserClaims;
+use crate::config::{Config, SimilarityConfig};
+
+// post마다 유지하는 nearest neighbor 수
+const GRAPH_NEIGHBORS: i64 = 5;
+// edge 갱신 시 무효화되지만, 놓친 경우를 대비한 상한
+const GRAPH_CACHE_TTL_SECS: u64 = 60 * 60;
+// GET /posts/:id/graph 기본값과 상한
+const DEFAULT_EGO_DEPTH: u32 = 2;
+const MAX_EGO_DEPTH: u32 = 4;
+const DEFAULT_EGO_LIMIT: usize = 50;
+const MAX_EGO_LIMIT: usize = 500;
 
-// Minimum similarity threshold for related posts (0.0 to 1.0)
-const MIN_SIMILARITY_THRESHOLD: f64 = 0.5;
-// Corresponding maximum distance (0.0 to 2.0)
-const MAX_DISTANCE_THRESHOLD: f64 = 2.0 * (1.0 - MIN_SIMILARITY_THRESHOLD);
+static GRAPH_CACHE: OnceCell<SharedConnection> = OnceCell::new();
 
-pub async fn get_related_post(post: &Post, db: &Pool<Postgres>) -> Vec<Post> {
+pub async fn get_related_post(
+    post: &Post,
+    db: &Pool<Postgres>,
+    config: &SimilarityConfig,
+) -> Vec<Post> {
     /* related post 가져오기기 */
     let Some(embedding) = &post.embedding else {
         return Vec::new();
     };
-    // 유사도 기반으로 관련 포스트 3개 반환
+    // 유사도 기반으로 관련 포스트 반환 (기본 3개, 임계값 미만 제외)
     let related_posts = sqlx::query_as::<_, Post>(
         r#"
         SELECT * FROM posts
         WHERE user_id = $1
         AND id != $2
         AND embedding IS NOT NULL
-        ORDER BY embedding <-> $3
-        LIMIT 3
+        AND embedding <=> $3 <= $5
+        ORDER BY embedding <=> $3
+        LIMIT $4
         "#,
     )
     .bind(post.user_id)
     .bind(post.id)
     .bind(embedding)
+    .bind(config.related_limit)
+    .bind(config.max_distance())
     .fetch_all(db)
     .await
     .unwrap_or_else(|e| {
-        eprintln!("DB error during related post fetch: {}", e);
+        error!(error = %e, "DB error during related post fetch");
         Vec::new()
     });
 
     related_posts
 }
 
+/// `GET /posts/graph`: the user's similarity graph, as `GraphData` JSON by
+/// default or exported with `?format=graphml|gexf|dot|json-ld`.
 pub async fn get_graph_data(
     State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
     JwtClaims(user): JwtClaims<UserClaims>,
-) -> Result<Json<GraphData>, (StatusCode, String)> {
-    let posts = sqlx::query_as::<_, PostGraphData>(
-        r#"SELECT id, title, embedding
-            FROM posts
+    Query(query): Query<GraphQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let graph = load_graph(&db, user.sub, &config.similarity).await?;
+    Ok(match query.format {
+        GraphFormat::Json => Json(graph).into_response(),
+        format => export::render(format, &graph),
+    })
+}
+
+/// Nodes and similarity links of `user_id`, served from the per-user cache when possible.
+pub async fn load_graph(
+    db: &Pool<Postgres>,
+    user_id: i64,
+    config: &SimilarityConfig,
+) -> Result<GraphData, (StatusCode, String)> {
+    if let Some(graph) = cached_graph(user_id).await {
+        return Ok(graph);
+    }
+
+    let nodes = load_nodes(db, user_id, None).await?;
+
+    // 미리 계산된 neighbor edge 중 임계값 이상만
+    let edges = sqlx::query_as::<_, (i64, i64, f32)>(
+        r#"SELECT source_id, target_id, similarity
+            FROM post_edges
             WHERE user_id = $1
-            AND embedding IS NOT NULL"#,
+            AND similarity >= $2"#,
     )
-    .bind(user.sub)
-    .fetch_all(&db)
+    .bind(user_id)
+    .bind(config.min_similarity as f32)
+    .fetch_all(db)
     .await
     .map_err(internal_error)?;
 
-    let nodes: Vec<GraphNode> = posts
-        .iter()
+    let links: Vec<GraphLink> = edges
+        .into_iter()
+        .map(|(source, target, similarity)| GraphLink {
+            source: source.to_string(),
+            target: target.to_string(),
+            value: similarity,
+        })
+        .collect();
+
+    let graph = GraphData { nodes, links };
+    store_graph(user_id, &graph).await;
+    Ok(graph)
+}
+
+#[derive(sqlx::FromRow)]
+struct NodeRow {
+    id: i64,
+    title: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    cluster_id: Option<i32>,
+    cluster_label: Option<String>,
+}
+
+/// Embedded posts of `user_id` as graph nodes, restricted to `ids` if given.
+async fn load_nodes(
+    db: &Pool<Postgres>,
+    user_id: i64,
+    ids: Option<&[i64]>,
+) -> Result<Vec<GraphNode>, (StatusCode, String)> {
+    let posts = sqlx::query_as::<_, NodeRow>(
+        r#"SELECT p.id, p.title, p.created_at, p.updated_at,
+                pc.cluster_id, c.label AS cluster_label
+            FROM posts p
+            LEFT JOIN post_clusters pc ON pc.post_id = p.id
+            LEFT JOIN clusters c ON c.user_id = pc.user_id AND c.cluster_id = pc.cluster_id
+            WHERE p.user_id = $1
+            AND p.embedding IS NOT NULL
+            AND ($2::BIGINT[] IS NULL OR p.id = ANY($2))"#,
+    )
+    .bind(user_id)
+    .bind(ids)
+    .fetch_all(db)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(posts
+        .into_iter()
         .map(|post| GraphNode {
             id: post.id.to_string(),
-            name: post.title.clone(),
+            name: post.title,
+            created_at: Some(post.created_at),
+            updated_at: Some(post.updated_at),
+            cluster_id: post.cluster_id,
+            cluster_label: post.cluster_label,
         })
-        .collect();
+        .collect())
+}
+
+/// `GET /posts/:id/graph`: the neighborhood of one post, expanded hop by hop
+/// along the nearest-neighbor edges (strongest first) up to `depth` hops or
+/// `limit` nodes.
+pub async fn get_ego_graph(
+    State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+    Path(id): Path<i64>,
+    Query(query): Query<EgoGraphQuery>,
+) -> Result<Json<GraphData>, (StatusCode, String)> {
+    let (depth, limit) = ego_bounds(&query);
+    if post_owner(&db, id).await != Some(user.sub) {
+        return Err((StatusCode::NOT_FOUND, "Post not found".to_string()));
+    }
+    let min_similarity = config.similarity.min_similarity as f32;
+
+    let mut included: Vec<i64> = vec![id];
+    let mut seen: HashSet<i64> = HashSet::from([id]);
+    let mut frontier = vec![id];
+    for _ in 0..depth {
+        if frontier.is_empty() || included.len() >= limit {
+            break;
+        }
+        // edge는 방향이 있으므로 양쪽 모두 이웃으로 취급
+        let candidates = sqlx::query_as::<_, (i64, f32)>(
+            r#"SELECT neighbor, MAX(similarity)
+                FROM (
+                    SELECT target_id AS neighbor, similarity FROM post_edges
+                    WHERE source_id = ANY($1) AND user_id = $2 AND similarity >= $3
+                    UNION ALL
+                    SELECT source_id AS neighbor, similarity FROM post_edges
+                    WHERE target_id = ANY($1) AND user_id = $2 AND similarity >= $3
+                ) e
+                GROUP BY neighbor
+                ORDER BY MAX(similarity) DESC"#,
+        )
+        .bind(&frontier)
+        .bind(user.sub)
+        .bind(min_similarity)
+        .fetch_all(&db)
+        .await
+        .map_err(internal_error)?;
 
-    // Helper struct for query result
-    #[derive(sqlx::FromRow)]
-    struct SimilarPair {
-        source: i64,
-        target: i64,
-        distance: Option<f64>,
+        frontier = next_frontier(candidates, &mut seen, limit - included.len());
+        included.extend(&frontier);
     }
 
-    // Calculate similarities and create links in a single query
-    let similar_pairs = sqlx::query_file_as!(
-        SimilarPair,
-        "sql/get_related_posts.sql",
-        user.sub,
-        MAX_DISTANCE_THRESHOLD
+    let nodes = load_nodes(&db, user.sub, Some(&included)).await?;
+    let edges = sqlx::query_as::<_, (i64, i64, f32)>(
+        r#"SELECT source_id, target_id, similarity
+            FROM post_edges
+            WHERE source_id = ANY($1)
+            AND target_id = ANY($1)
+            AND similarity >= $2"#,
     )
+    .bind(&included)
+    .bind(min_similarity)
     .fetch_all(&db)
     .await
     .map_err(internal_error)?;
 
-    let links: Vec<GraphLink> = similar_pairs
+    let links = edges
         .into_iter()
-        .filter_map(|pair| {
-            pair.distance.map(|dist| GraphLink {
-                source: pair.source.to_string(),
-                target: pair.target.to_string(),
-                // 코사인 distance(0~2) → similarity(0~1)
-                value: (1.0 - (dist / 2.0)) as f32,
-            })
+        .map(|(source, target, similarity)| GraphLink {
+            source: source.to_string(),
+            target: target.to_string(),
+            value: similarity,
         })
         .collect();
-
     Ok(Json(GraphData { nodes, links }))
 }
+
+// 기본값 적용 후 (depth, limit)을 허용 범위로 제한
+fn ego_bounds(query: &EgoGraphQuery) -> (u32, usize) {
+    let depth = query.depth.unwrap_or(DEFAULT_EGO_DEPTH).min(MAX_EGO_DEPTH);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_EGO_LIMIT)
+        .clamp(1, MAX_EGO_LIMIT);
+    (depth, limit)
+}
+
+// 유사도 순으로 정렬된 후보 중 처음 보는 post를 최대 `room`개까지 다음 hop으로
+fn next_frontier(candidates: Vec<(i64, f32)>, seen: &mut HashSet<i64>, room: usize) -> Vec<i64> {
+    candidates
+        .into_iter()
+        .map(|(neighbor, _)| neighbor)
+        .filter(|neighbor| seen.insert(*neighbor))
+        .take(room)
+        .collect()
+}
+
+/// Recomputes the neighbor edges touching `post_id` after its embedding changed.
+///
+/// Besides the post's own top-k, posts that pointed at it or that it now
+/// points at are recomputed too, since their ranking may have shifted.
+pub async fn update_edges(db: &Pool<Postgres>, post_id: i64) -> Result<(), sqlx::Error> {
+    let mut affected = edge_sources(db, post_id).await?;
+    affected.push(post_id);
+    refresh_edges(db, &affected).await?;
+
+    let targets: Vec<i64> =
+        sqlx::query_scalar("SELECT target_id FROM post_edges WHERE source_id = $1")
+            .bind(post_id)
+            .fetch_all(db)
+            .await?;
+    refresh_edges(db, &targets).await?;
+
+    if let Some(user_id) = post_owner(db, post_id).await {
+        invalidate_graph(user_id).await;
+        cluster::schedule(user_id);
+    }
+    Ok(())
+}
+
+/// Posts with a neighbor edge pointing at `post_id`. Collect them before the
+/// post is deleted and pass them to [`refresh_edges`] afterwards, so they get
+/// a new neighbor in place of it.
+pub async fn edge_sources(db: &Pool<Postgres>, post_id: i64) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT source_id FROM post_edges WHERE target_id = $1")
+        .bind(post_id)
+        .fetch_all(db)
+        .await
+}
+
+/// Recomputes the top-k neighbor edges of `sources`.
+pub async fn refresh_edges(db: &Pool<Postgres>, sources: &[i64]) -> Result<(), sqlx::Error> {
+    if sources.is_empty() {
+        return Ok(());
+    }
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM post_edges WHERE source_id = ANY($1)")
+        .bind(sources)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(include_str!("../../sql/refresh_post_edges.sql"))
+        .bind(sources)
+        .bind(GRAPH_NEIGHBORS)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Recomputes every neighbor edge (of `user_id`, or of everyone), e.g. after
+/// switching embedding models.
+///
+/// Pass a transaction so readers never see the edges half rebuilt.
+pub async fn rebuild_edges(
+    conn: &mut PgConnection,
+    user_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    let sources: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM posts WHERE embedding IS NOT NULL AND ($1::BIGINT IS NULL OR user_id = $1)",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    sqlx::query("DELETE FROM post_edges WHERE $1::BIGINT IS NULL OR user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(include_str!("../../sql/refresh_post_edges.sql"))
+        .bind(&sources)
+        .bind(GRAPH_NEIGHBORS)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+pub fn init(client: redis::Client) {
+    let _ = GRAPH_CACHE.set(SharedConnection::new(client));
+}
+
+fn graph_key(user_id: i64) -> String {
+    format!("graph:user:{user_id}")
+}
+
+async fn cached_graph(user_id: i64) -> Option<GraphData> {
+    let cache = GRAPH_CACHE.get()?;
+    let mut conn = cache.get().await.ok()?;
+    match conn.get::<_, Option<String>>(graph_key(user_id)).await {
+        Ok(raw) => raw.and_then(|raw| serde_json::from_str(&raw).ok()),
+        Err(e) => {
+            warn!(user_id, error = %e, "Failed to read cached graph");
+            cache.reset().await;
+            None
+        }
+    }
+}
+
+async fn store_graph(user_id: i64, graph: &GraphData) {
+    let Some(cache) = GRAPH_CACHE.get() else {
+        return;
+    };
+    let Ok(raw) = serde_json::to_string(graph) else {
+        return;
+    };
+    let result = match cache.get().await {
+        Ok(mut conn) => {
+            conn.set_ex::<_, _, ()>(graph_key(user_id), raw, GRAPH_CACHE_TTL_SECS)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(user_id, error = %e, "Failed to cache graph");
+        cache.reset().await;
+    }
+}
+
+/// Drops the cached graph of `user_id` (titles, edges or the post set changed).
+pub async fn invalidate_graph(user_id: i64) {
+    let Some(cache) = GRAPH_CACHE.get() else {
+        return;
+    };
+    let result = match cache.get().await {
+        Ok(mut conn) => conn.del::<_, ()>(graph_key(user_id)).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(user_id, error = %e, "Failed to invalidate cached graph");
+        cache.reset().await;
+    }
+}
+
+/// Same as [`invalidate_graph`] for the owner of `post_id`, also queueing
+/// reclustering; call before deleting it.
+pub async fn invalidate_post_graph(db: &Pool<Postgres>, post_id: i64) {
+    if let Some(user_id) = post_owner(db, post_id).await {
+        invalidate_graph(user_id).await;
+        cluster::schedule(user_id);
+    }
+}
+
+async fn post_owner(db: &Pool<Postgres>, post_id: i64) -> Option<i64> {
+    sqlx::query_scalar("SELECT user_id FROM posts WHERE id = $1")
+        .bind(post_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or_else(|e| {
+            error!(post_id, error = %e, "DB error while looking up post owner");
+            None
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(depth: Option<u32>, limit: Option<usize>) -> EgoGraphQuery {
+        EgoGraphQuery { depth, limit }
+    }
+
+    #[test]
+    fn ego_bounds_applies_defaults_and_caps() {
+        assert_eq!(
+            ego_bounds(&query(None, None)),
+            (DEFAULT_EGO_DEPTH, DEFAULT_EGO_LIMIT)
+        );
+        assert_eq!(
+            ego_bounds(&query(Some(99), Some(99_999))),
+            (MAX_EGO_DEPTH, MAX_EGO_LIMIT)
+        );
+        assert_eq!(ego_bounds(&query(Some(0), Some(0))), (0, 1));
+    }
+
+    #[test]
+    fn next_frontier_skips_seen_posts() {
+        let mut seen = HashSet::from([1, 2]);
+        let frontier = next_frontier(vec![(2, 0.9), (3, 0.8), (1, 0.7), (4, 0.6)], &mut seen, 10);
+        assert_eq!(frontier, vec![3, 4]);
+        assert_eq!(seen, HashSet::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn next_frontier_keeps_strongest_within_room() {
+        let mut seen = HashSet::from([1]);
+        let frontier = next_frontier(vec![(5, 0.9), (6, 0.8), (7, 0.7)], &mut seen, 2);
+        assert_eq!(frontier, vec![5, 6]);
+        // 잘린 후보는 다음 hop에서 다시 고려될 수 있도록 seen에 남기지 않음
+        assert!(!seen.contains(&7));
+    }
+}
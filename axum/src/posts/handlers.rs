@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Json, Path, Query, State},
     http::StatusCode,
@@ -6,14 +8,15 @@ use jwt_authorizer::JwtClaims;
 use pgvector::Vector;
 use serde::{Deserialize, Serialize};
 use sqlx::{Postgres, pool::Pool};
+use tracing::{error, info, warn};
 
-use super::graph::get_related_post;
+use super::embedder::{self, Embedder};
+use super::graph::{self, get_related_post};
 use super::models::*;
-use super::models::{EmbeddingRequest, EmbeddingResponse, QueryRequest};
-use super::utils::{
-    EmbeddingApiError, call_embedding_api_with_retry, check_embedding_api_health, internal_error,
-};
+use super::utils::{EmbeddingApiError, internal_error};
+use super::{cluster, dirty, journal, reembed, related, search, snippet};
 use crate::auth::UserClaims;
+use crate::config::Config;
 
 pub async fn create_post(
     State(db): State<Pool<Postgres>>,
@@ -34,7 +37,7 @@ pub async fn create_post(
     .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
-        eprintln!("Error inserting post: {}", e);
+        error!(error = %e, "Error inserting post");
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to create post".to_string(),
@@ -42,6 +45,8 @@ pub async fn create_post(
     })?;
 
     tx.commit().await.map_err(internal_error)?;
+    // 응답 전에 캐시된 graph를 버려서 새 post가 바로 보이도록
+    graph::invalidate_graph(user.sub).await;
 
     Ok(Json(post))
 }
@@ -50,7 +55,7 @@ pub async fn list_posts(
     State(db): State<Pool<Postgres>>,
     JwtClaims(user): JwtClaims<UserClaims>,
 ) -> Json<Vec<Post>> {
-    let posts = sqlx::query_as::<_, Post>(
+    let mut posts = sqlx::query_as::<_, Post>(
         "SELECT * FROM posts WHERE user_id = $1 
     ORDER BY created_at DESC;",
     )
@@ -58,6 +63,8 @@ pub async fn list_posts(
     .fetch_all(&db)
     .await
     .unwrap();
+    // 아직 flush되지 않은 캐시 수정 반영
+    dirty::overlay(&mut posts).await;
 
     //TODO : unwrap 사용 안하는게 좋음
     Json(posts)
@@ -65,6 +72,7 @@ pub async fn list_posts(
 
 pub async fn get_posts(
     State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
     Path(id): Path<i64>,
     JwtClaims(user): JwtClaims<UserClaims>,
 ) -> Result<Json<PostResponse>, (StatusCode, String)> {
@@ -74,8 +82,14 @@ pub async fn get_posts(
         .fetch_one(&db)
         .await
         .map_err(|_| (StatusCode::NOT_FOUND, "Post not found".to_string()))?;
+    // 캐시 사본이 만료됐어도 아직 flush되지 않은 write 반영
+    let mut posts = vec![post];
+    dirty::overlay(&mut posts).await;
+    let post = posts
+        .pop()
+        .ok_or((StatusCode::NOT_FOUND, "Post not found".to_string()))?;
 
-    let related_posts = get_related_post(&post, &db).await;
+    let related_posts = get_related_post(&post, &db, &config.similarity).await;
     let post_response = PostResponse {
         id: post.id,
         title: post.title,
@@ -94,42 +108,101 @@ pub async fn delete_post(
     Path(id): Path<i64>,
     JwtClaims(user): JwtClaims<UserClaims>,
 ) -> Result<(), (StatusCode, String)> {
+    // 삭제되면 cascade로 edge가 사라지므로 먼저 조회
+    let sources = graph::edge_sources(&db, id).await.unwrap_or_else(|e| {
+        error!(post_id = id, error = %e, "Failed to look up graph edges");
+        Vec::new()
+    });
     sqlx::query("DELETE FROM posts WHERE id = $1 and user_id = $2")
         .bind(id)
         .bind(user.sub)
         .execute(&db)
         .await
         .map_err(|_| (StatusCode::NOT_FOUND, "Post not found".to_string()))?;
+    journal::forget(id).await;
+    if let Err(e) = graph::refresh_edges(&db, &sources).await {
+        error!(post_id = id, error = %e, "Failed to update graph edges");
+    }
+    graph::invalidate_graph(user.sub).await;
+    cluster::schedule(user.sub);
 
     Ok(())
 }
 
 pub async fn update_post(
     State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
     JwtClaims(user): JwtClaims<UserClaims>,
     Path(id): Path<i64>,
     Json(payload): Json<UpdatePost>,
 ) -> Result<Json<PostResponse>, (StatusCode, String)> {
+    // cache flush와 같은 방식으로 새 content를 embedding (tx 밖에서 호출)
+    let embedded = match &payload.content {
+        Some(content) => Some(embed_content(&db, content).await?),
+        None => None,
+    };
     let mut tx = db.begin().await.map_err(internal_error)?;
 
-    sqlx::query("UPDATE posts SET title = $1, content = $2 WHERE id = $3 AND user_id = $4")
-        .bind(&payload.title)
-        .bind(&payload.content)
-        .bind(id)
-        .bind(user.sub)
-        .execute(&mut *tx)
-        .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "Post not found".to_string()))?;
+    // autosave처럼 일부 필드만 보낸 PUT은 나머지를 유지.
+    // content가 바뀌면 embedding을 교체하고, 이전 내용으로 만든 embedding_next는
+    // cutover에서 올라가지 않도록 비움
+    let updated = sqlx::query(
+        r#"UPDATE posts
+            SET title = COALESCE($1, title),
+                content = COALESCE($2, content),
+                embedding = CASE WHEN $2::TEXT IS NULL THEN embedding ELSE $5 END,
+                embedding_model = CASE WHEN $2::TEXT IS NULL THEN embedding_model ELSE $6 END,
+                embedding_dim = CASE WHEN $2::TEXT IS NULL THEN embedding_dim ELSE $7 END,
+                embedding_next = CASE WHEN $2::TEXT IS NULL THEN embedding_next END,
+                embedding_next_model = CASE WHEN $2::TEXT IS NULL THEN embedding_next_model END
+            WHERE id = $3 AND user_id = $4"#,
+    )
+    .bind(&payload.title)
+    .bind(&payload.content)
+    .bind(id)
+    .bind(user.sub)
+    .bind(
+        embedded
+            .as_ref()
+            .and_then(|e| e.as_ref().map(|(vector, _)| vector)),
+    )
+    .bind(
+        embedded
+            .as_ref()
+            .and_then(|e| e.as_ref().map(|(_, model)| model)),
+    )
+    .bind(
+        embedded
+            .as_ref()
+            .and_then(|e| e.as_ref().map(|(vector, _)| vector.as_slice().len() as i32)),
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Post not found".to_string()));
+    }
 
-    let post = sqlx::query_as::<_, Post>("SELECT * FROM posts WHERE id = $1")
+    let post = sqlx::query_as::<_, Post>("SELECT * FROM posts WHERE id = $1 AND user_id = $2")
         .bind(id)
+        .bind(user.sub)
         .fetch_one(&mut *tx)
         .await
         .map_err(|_| (StatusCode::NOT_FOUND, "Post not found".to_string()))?;
 
     tx.commit().await.map_err(internal_error)?;
+    // cache를 거치지 않고 바로 반영된 write (breaker 우회 포함)
+    journal::truncate(post.id, &post.title, &post.content).await;
+    // cache flush 경로와 같이 neighbor edge 갱신 (graph 캐시 무효화 포함)
+    if let Err(e) = graph::update_edges(&db, id).await {
+        error!(post_id = id, error = %e, "Failed to update graph edges");
+        graph::invalidate_graph(user.sub).await;
+    }
+    if embedded.is_some() {
+        related::refresh(id).await;
+    }
 
-    let related_posts = get_related_post(&post, &db).await;
+    let related_posts = get_related_post(&post, &db, &config.similarity).await;
 
     let post_response: PostResponse = PostResponse {
         id: post.id,
@@ -145,109 +218,175 @@ pub async fn update_post(
 
 pub async fn search_posts(
     State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    State(embedder): State<Embedder>,
     JwtClaims(user): JwtClaims<UserClaims>,
     Query(search_query): Query<SearchQuery>,
-) -> Result<Json<Vec<Post>>, (StatusCode, String)> {
-    println!("Received search query in Axum: {}", search_query.q);
-
-    let embed_api_url =
-        std::env::var("EMBED_API_URL").unwrap_or_else(|_| "http://embed_api:8001".to_string());
-    let health_check_url = format!("{}/health", embed_api_url);
-    let query_embedding_url = format!("{}/query-embedding", embed_api_url);
+) -> Result<Json<Vec<SearchHit>>, (StatusCode, String)> {
+    info!(query = %search_query.q, "Received search query");
+    let parsed = search::parse(&search_query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let filters = &parsed.filters;
 
-    // 헬스체크 수행
-    check_embedding_api_health(&health_check_url)
-        .await
-        .map_err(|e| {
-            eprintln!("Embedding API health check failed: {}", e);
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                "Embedding service unavailable".to_string(),
-            )
-        })?;
-
-    let embed_data = call_embedding_api_with_retry(
-        &query_embedding_url,
-        QueryRequest {
-            query: search_query.q.clone(),
-        },
-    )
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to get query embedding from API: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to get query embedding".to_string(),
-        )
-    })?;
+    // filter만 있으면 embedding 없이 최근 수정 순
+    let query_vector = if parsed.text.is_empty() {
+        None
+    } else {
+        // 저장된 embedding과 같은 모델로 query embedding
+        let model = reembed::active_model(&db, &config.embedding.model)
+            .await
+            .map_err(internal_error)?;
+        let dimension = reembed::active_dimension(&db, &model)
+            .await
+            .map_err(internal_error)?;
 
-    let query_vector = Vector::from(embed_data.embedding);
+        // health는 background monitor가 확인; cache hit이면 embedding 서비스를 거치지 않음
+        let embedding = embedder
+            .embed_query(parsed.text.clone(), model, dimension)
+            .await
+            .map_err(|e| match e {
+                EmbeddingApiError::Unhealthy => {
+                    error!("Embedding API is unhealthy, rejecting search");
+                    (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Embedding service unavailable".to_string(),
+                    )
+                }
+                e => {
+                    error!(error = %e, "Failed to get query embedding from API");
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to get query embedding".to_string(),
+                    )
+                }
+            })?;
+        Some(Vector::from(embedding))
+    };
 
-    let posts = sqlx::query_as::<_, Post>(include_str!("../../sql/search_posts.sql"))
+    let patterns = |values: &[String], prefix: &str, suffix: &str| -> Vec<String> {
+        values
+            .iter()
+            .map(|value| search::like_pattern(value, prefix, suffix))
+            .collect()
+    };
+    let tags = |tags: &[String]| -> Vec<String> {
+        tags.iter().map(|tag| search::tag_pattern(tag)).collect()
+    };
+    let rows = sqlx::query_as::<_, SearchRow>(include_str!("../../sql/search_posts.sql"))
         .bind(query_vector)
         .bind(user.sub)
+        .bind(filters.created.from)
+        .bind(filters.created.until)
+        .bind(filters.updated.from)
+        .bind(filters.updated.until)
+        .bind(patterns(&filters.prefixes, "", "%"))
+        .bind(patterns(&filters.excluded_prefixes, "", "%"))
+        .bind(tags(&filters.tags))
+        .bind(tags(&filters.excluded_tags))
+        .bind(patterns(&filters.phrases, "%", "%"))
+        .bind(patterns(&filters.excluded, "%", "%"))
+        .bind(filters.has_embedding)
         .fetch_all(&db)
         .await
         .map_err(|e| {
-            eprintln!("Database search failed: {}", e);
+            error!(error = %e, "Database search failed");
             internal_error(e)
         })?;
+    let scores: HashMap<i64, f64> = rows.iter().map(|row| (row.post.id, row.score)).collect();
+    let mut posts: Vec<Post> = rows.into_iter().map(|row| row.post).collect();
+    // snippet은 아직 flush되지 않은 내용 기준으로 만듦
+    dirty::overlay(&mut posts).await;
+
+    let snippets = snippet::search_snippets(&posts, &parsed.terms);
+    let hits = posts
+        .into_iter()
+        .zip(snippets)
+        .map(|(post, snippet)| SearchHit {
+            score: scores.get(&post.id).copied().unwrap_or_default(),
+            matched_field: snippet.matched_field,
+            snippet: snippet.text,
+            highlights: snippet.highlights,
+            post,
+        })
+        .collect();
 
-    Ok(Json(posts))
+    Ok(Json(hits))
 }
 
 pub async fn __update_post_from_cache(
     State(db): State<Pool<Postgres>>,
     Path(id): Path<i64>,
     Json(payload): Json<UpdatePost>,
-) {
-    let mut tx = db.begin().await.map_err(internal_error).unwrap();
-
-    let embed_api_url =
-        std::env::var("EMBED_API_URL").unwrap_or_else(|_| "http://embed_api:8001".to_string());
-    let health_check_url = format!("{}/health", embed_api_url);
-    let embed_url = format!("{}/embed", embed_api_url);
-
-    let mut vector: Option<Vector> = None;
-    // 헬스체크 수행
-    if let Err(e) = check_embedding_api_health(&health_check_url).await {
-        eprintln!("Embedding API health check failed for update: {}", e);
-    } else {
-        vector = if let Some(content) = payload.content.clone() {
-            let embedding_result =
-                call_embedding_api_with_retry(&embed_url, EmbeddingRequest { text: content }).await;
-
-            match embedding_result {
-                Ok(data) => {
-                    // ➋ pgvector Vector로 변환
-                    Some(Vector::from(data.embedding))
-                }
-                Err(e) => {
-                    eprintln!("Failed to get embedding for update, setting to NULL: {}", e);
-                    None // Set embedding to NULL
-                }
-            }
-        } else {
-            eprintln!("Payload content is None, setting embedding to NULL.");
+) -> Result<(), (StatusCode, String)> {
+    let embedded = match &payload.content {
+        Some(content) => embed_content(&db, content).await?,
+        None => {
+            warn!("Payload content is None, setting embedding to NULL.");
             None
-        };
-    }
-
-    sqlx::query(
+        }
+    };
+    // embedding을 기다리는 동안 connection을 잡지 않도록 여기서 시작
+    let mut tx = db.begin().await.map_err(internal_error)?;
+    // 재embedding 중이면 embedding_next를 비워서 worker가 새 내용으로 다시 만들게 함
+    let updated = sqlx::query(
         r#"
         UPDATE posts 
-        SET title = $1, content = $2 , embedding = $3
+        SET title = $1, content = $2 , embedding = $3,
+            embedding_model = $5, embedding_dim = $6,
+            embedding_next = NULL, embedding_next_model = NULL
         WHERE id = $4
         "#,
     )
     .bind(&payload.title)
     .bind(&payload.content)
-    .bind(vector)
+    .bind(embedded.as_ref().map(|(vector, _)| vector))
     .bind(id)
+    .bind(embedded.as_ref().map(|(_, model)| model))
+    .bind(
+        embedded
+            .as_ref()
+            .map(|(vector, _)| vector.as_slice().len() as i32),
+    )
     .execute(&mut *tx)
     .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "Post not found".to_string()))
-    .unwrap();
+    .map_err(internal_error)?;
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Post not found".to_string()));
+    }
+
+    tx.commit().await.map_err(internal_error)?;
 
-    tx.commit().await.map_err(internal_error).unwrap();
+    // embedding이 바뀌었으므로 neighbor edge 갱신
+    if let Err(e) = graph::update_edges(&db, id).await {
+        error!(post_id = id, error = %e, "Failed to update graph edges");
+    }
+    Ok(())
+}
+
+/// Embeds new post content with the active model. `None` when the service is
+/// unhealthy or the call fails; the post is then stored without an embedding
+/// until it is re-embedded.
+async fn embed_content(
+    db: &Pool<Postgres>,
+    content: &str,
+) -> Result<Option<(Vector, String)>, (StatusCode, String)> {
+    let embedder = embedder::get();
+    // background monitor가 기록한 상태로 판단 (write마다 /health를 호출하지 않음)
+    if !embedder.is_healthy() {
+        warn!("Embedding API is unhealthy, setting embedding to NULL for update");
+        return Ok(None);
+    }
+    let model = reembed::active_model(db, embedder.default_model())
+        .await
+        .map_err(internal_error)?;
+    // 동시에 flush되는 post들은 embedder가 한 번의 /embed 호출로 묶음
+    match embedder
+        .embed(content.to_string(), Some(model.clone()))
+        .await
+    {
+        Ok(data) => Ok(Some((Vector::from(data.embedding), model))),
+        Err(e) => {
+            warn!(error = %e, "Failed to get embedding for update, setting to NULL");
+            Ok(None)
+        }
+    }
 }
@@ -0,0 +1,772 @@
+// src/posts/journal.rs
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Json, Path, State},
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jwt_authorizer::JwtClaims;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, pool::Pool};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+use tracing::{error, info, warn};
+
+use super::cache::flush_delete;
+use super::handlers::__update_post_from_cache;
+use super::models::UpdatePost;
+use crate::auth::UserClaims;
+use crate::config::{Durability, JournalBackend, JournalConfig};
+
+// PUT body 최대 크기 (journal에 기록하기 위해 버퍼링)
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+static JOURNAL: OnceCell<Journal> = OnceCell::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    #[error("journal file error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("journal table error: {0}")]
+    Db(#[from] sqlx::Error),
+    #[error("journal encoding error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// One accepted `PUT` or `DELETE /posts/:id`, kept until the write-back flush
+/// stores it.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PendingWrite {
+    pub seq: i64,
+    pub post_id: i64,
+    pub user_id: i64,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// A write as it is journaled.
+enum Write<'a> {
+    Update(&'a UpdatePost),
+    Delete,
+}
+
+/// Write-ahead log for post updates and deletes absorbed by the write-back cache.
+///
+/// In durable mode every write is appended here before the request is
+/// acknowledged, so it survives losing Redis before the flush callback runs.
+enum Journal {
+    File(FileJournal),
+    Postgres(Pool<Postgres>),
+}
+
+struct FileJournal {
+    path: PathBuf,
+    state: Mutex<FileState>,
+}
+
+struct FileState {
+    next_seq: i64,
+    entries: Vec<PendingWrite>,
+}
+
+/// Opens the journal in durable mode; fast mode leaves it disabled.
+pub async fn init(config: &JournalConfig, db: &Pool<Postgres>) -> Result<(), JournalError> {
+    if config.mode == Durability::Fast {
+        return Ok(());
+    }
+
+    let journal = match config.backend {
+        JournalBackend::Postgres => Journal::Postgres(db.clone()),
+        JournalBackend::File => {
+            let path = PathBuf::from(&config.path);
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir).await?;
+            }
+            let entries = read_file(&path).await?;
+            let next_seq = entries.iter().map(|entry| entry.seq).max().unwrap_or(0) + 1;
+            Journal::File(FileJournal {
+                path,
+                state: Mutex::new(FileState { next_seq, entries }),
+            })
+        }
+    };
+    info!(backend = ?config.backend, "Write-ahead journal enabled");
+    let _ = JOURNAL.set(journal);
+    Ok(())
+}
+
+async fn read_file(path: &PathBuf) -> Result<Vec<PendingWrite>, JournalError> {
+    let raw = match fs::read_to_string(path).await {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = Vec::new();
+    for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            // crash 도중 잘린 마지막 줄
+            Err(e) => warn!(error = %e, "Skipping unreadable journal line"),
+        }
+    }
+    Ok(entries)
+}
+
+impl Journal {
+    async fn append(
+        &self,
+        post_id: i64,
+        user_id: i64,
+        write: Write<'_>,
+    ) -> Result<i64, JournalError> {
+        let (title, content, deleted) = match write {
+            Write::Update(update) => (update.title.clone(), update.content.clone(), false),
+            Write::Delete => (None, None, true),
+        };
+        match self {
+            Journal::Postgres(db) => {
+                let seq = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO pending_writes (post_id, user_id, title, content, deleted)
+                    VALUES ($1, $2, $3, $4, $5)
+                    RETURNING seq
+                    "#,
+                )
+                .bind(post_id)
+                .bind(user_id)
+                .bind(&title)
+                .bind(&content)
+                .bind(deleted)
+                .fetch_one(db)
+                .await?;
+                Ok(seq)
+            }
+            Journal::File(journal) => {
+                let mut state = journal.state.lock().await;
+                let entry = PendingWrite {
+                    seq: state.next_seq,
+                    post_id,
+                    user_id,
+                    title,
+                    content,
+                    deleted,
+                };
+                let mut line = serde_json::to_vec(&entry)?;
+                line.push(b'\n');
+
+                let mut file = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&journal.path)
+                    .await?;
+                file.write_all(&line).await?;
+                file.sync_data().await?;
+
+                state.next_seq += 1;
+                state.entries.push(entry.clone());
+                Ok(entry.seq)
+            }
+        }
+    }
+
+    async fn entries(&self) -> Result<Vec<PendingWrite>, JournalError> {
+        match self {
+            Journal::Postgres(db) => Ok(sqlx::query_as::<_, PendingWrite>(
+                "SELECT seq, post_id, user_id, title, content, deleted FROM pending_writes ORDER BY seq",
+            )
+            .fetch_all(db)
+            .await?),
+            Journal::File(journal) => Ok(journal.state.lock().await.entries.clone()),
+        }
+    }
+
+    /// Entries of one post, in journal order (uses `pending_writes_post_id_idx`).
+    async fn entries_for(&self, post_id: i64) -> Result<Vec<PendingWrite>, JournalError> {
+        match self {
+            Journal::Postgres(db) => Ok(sqlx::query_as::<_, PendingWrite>(
+                r#"SELECT seq, post_id, user_id, title, content, deleted
+                    FROM pending_writes
+                    WHERE post_id = $1
+                    ORDER BY seq"#,
+            )
+            .bind(post_id)
+            .fetch_all(db)
+            .await?),
+            Journal::File(journal) => Ok(journal
+                .state
+                .lock()
+                .await
+                .entries
+                .iter()
+                .filter(|entry| entry.post_id == post_id)
+                .cloned()
+                .collect()),
+        }
+    }
+
+    /// Removes exactly the entries `seqs`; other writes of the same post stay
+    /// for replay.
+    async fn remove(&self, seqs: &[i64]) -> Result<(), JournalError> {
+        if seqs.is_empty() {
+            return Ok(());
+        }
+        match self {
+            Journal::Postgres(db) => {
+                sqlx::query("DELETE FROM pending_writes WHERE seq = ANY($1)")
+                    .bind(seqs)
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            Journal::File(journal) => {
+                let mut state = journal.state.lock().await;
+                state.entries.retain(|entry| !seqs.contains(&entry.seq));
+                rewrite(&journal.path, &state.entries).await
+            }
+        }
+    }
+
+    /// Removes every entry of `post_id`.
+    async fn remove_post(&self, post_id: i64) -> Result<(), JournalError> {
+        match self {
+            Journal::Postgres(db) => {
+                sqlx::query("DELETE FROM pending_writes WHERE post_id = $1")
+                    .bind(post_id)
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            Journal::File(journal) => {
+                let mut state = journal.state.lock().await;
+                state.entries.retain(|entry| entry.post_id != post_id);
+                rewrite(&journal.path, &state.entries).await
+            }
+        }
+    }
+}
+
+// 임시 파일에 쓰고 rename해서 truncate 도중 crash에도 journal이 깨지지 않게 함
+async fn rewrite(path: &PathBuf, entries: &[PendingWrite]) -> Result<(), JournalError> {
+    let mut buf = Vec::new();
+    for entry in entries {
+        serde_json::to_writer(&mut buf, entry)?;
+        buf.push(b'\n');
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp).await?;
+    file.write_all(&buf).await?;
+    file.sync_data().await?;
+    fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Journals `PUT` and `DELETE /posts/:id` before the cache layer acknowledges
+/// them.
+///
+/// A write that cannot be journaled is refused with 503 instead of being
+/// accepted without the durability guarantee.
+pub async fn record(
+    JwtClaims(user): JwtClaims<UserClaims>,
+    Path(id): Path<i64>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(journal) = JOURNAL.get() else {
+        return next.run(req).await;
+    };
+    if req.method() == Method::DELETE {
+        return record_delete(journal, id, user.sub, req, next).await;
+    }
+    if req.method() != Method::PUT {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Body too large").into_response(),
+    };
+    let Ok(update) = serde_json::from_slice::<UpdatePost>(&bytes) else {
+        // 잘못된 body는 handler의 Json extractor가 거절하도록 그대로 넘김
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+
+    let seq = match journal.append(id, user.sub, Write::Update(&update)).await {
+        Ok(seq) => seq,
+        Err(e) => {
+            error!(post_id = id, error = %e, "Failed to journal post update");
+            return not_persisted();
+        }
+    };
+
+    let res = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+    if !res.status().is_success() {
+        // 받아들여지지 않은 write는 replay하지 않음
+        if let Err(e) = journal.remove(&[seq]).await {
+            warn!(post_id = id, error = %e, "Failed to drop rejected write from journal");
+        }
+    }
+    res
+}
+
+async fn record_delete(
+    journal: &Journal,
+    id: i64,
+    user_id: i64,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let seq = match journal.append(id, user_id, Write::Delete).await {
+        Ok(seq) => seq,
+        Err(e) => {
+            error!(post_id = id, error = %e, "Failed to journal post delete");
+            return not_persisted();
+        }
+    };
+    let res = next.run(req).await;
+    if !res.status().is_success()
+        && let Err(e) = journal.remove(&[seq]).await
+    {
+        warn!(post_id = id, error = %e, "Failed to drop rejected delete from journal");
+    }
+    res
+}
+
+fn not_persisted() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Write could not be persisted, try again".to_string(),
+    )
+        .into_response()
+}
+
+/// Drops every journaled write of a post once it is deleted from Postgres.
+pub async fn forget(post_id: i64) {
+    let Some(journal) = JOURNAL.get() else {
+        return;
+    };
+    if let Err(e) = journal.remove_post(post_id).await {
+        warn!(post_id, error = %e, "Failed to drop deleted post from journal");
+    }
+}
+
+/// Drops the journaled writes of a post once `title` / `content` are in Postgres.
+///
+/// Only entries the flushed values account for are dropped (see
+/// [`covered_by_flush`]); anything else stays for the next flush or replay.
+pub async fn truncate(post_id: i64, title: &str, content: &str) {
+    let Some(journal) = JOURNAL.get() else {
+        return;
+    };
+    let entries = match journal.entries_for(post_id).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!(post_id, error = %e, "Failed to read journal for truncation");
+            return;
+        }
+    };
+    let covered = covered_by_flush(&entries, post_id, title, content);
+    if let Err(e) = journal.remove(&covered).await {
+        warn!(post_id, error = %e, "Failed to truncate journal");
+    }
+}
+
+/// Seqs of `post_id`'s updates that a flush of `title` / `content` made
+/// redundant.
+///
+/// An entry is covered when every field it sets now holds its value in
+/// Postgres, or was overwritten by a newer entry whose value does. An entry
+/// the cache has not merged yet differs from the flushed value and stays.
+fn covered_by_flush(
+    entries: &[PendingWrite],
+    post_id: i64,
+    title: &str,
+    content: &str,
+) -> Vec<i64> {
+    let updates: Vec<&PendingWrite> = entries
+        .iter()
+        .filter(|entry| entry.post_id == post_id && !entry.deleted)
+        .collect();
+    // 필드마다 flush된 값과 같은 가장 최근 entry
+    let newest = |field: fn(&PendingWrite) -> Option<&str>, value: &str| {
+        updates
+            .iter()
+            .filter(|entry| field(entry) == Some(value))
+            .map(|entry| entry.seq)
+            .max()
+    };
+    let title_seq = newest(|entry| entry.title.as_deref(), title);
+    let content_seq = newest(|entry| entry.content.as_deref(), content);
+
+    let covered = |value: Option<&str>, flushed: &str, seq: i64, newest: Option<i64>| {
+        value.is_none_or(|value| value == flushed || newest.is_some_and(|newest| newest > seq))
+    };
+    updates
+        .iter()
+        .filter(|entry| {
+            covered(entry.title.as_deref(), title, entry.seq, title_seq)
+                && covered(entry.content.as_deref(), content, entry.seq, content_seq)
+        })
+        .map(|entry| entry.seq)
+        .collect()
+}
+
+/// Journaled writes of one post folded in journal order.
+#[derive(Debug)]
+struct Merged {
+    user_id: i64,
+    /// Entries folded in, removed once the writes are applied.
+    seqs: Vec<i64>,
+    update: UpdatePost,
+    deleted: bool,
+}
+
+// post마다 순서대로 합쳐서 한 번만 반영
+fn merge(entries: Vec<PendingWrite>) -> BTreeMap<i64, Merged> {
+    let mut pending: BTreeMap<i64, Merged> = BTreeMap::new();
+    for entry in entries {
+        let merged = pending.entry(entry.post_id).or_insert(Merged {
+            user_id: entry.user_id,
+            seqs: Vec::new(),
+            update: UpdatePost {
+                title: None,
+                content: None,
+            },
+            deleted: false,
+        });
+        merged.seqs.push(entry.seq);
+        if merged.user_id != entry.user_id {
+            warn!(
+                post_id = entry.post_id,
+                seq = entry.seq,
+                "Journal entry from another user, skipping"
+            );
+            continue;
+        }
+        // 삭제 뒤의 update는 의미 없음
+        merged.deleted |= entry.deleted;
+        if entry.title.is_some() {
+            merged.update.title = entry.title;
+        }
+        if entry.content.is_some() {
+            merged.update.content = entry.content;
+        }
+    }
+    pending
+}
+
+// title만 / content만 바꾼 write는 나머지를 지금 row 값으로 채워서 반영
+fn fill_missing(update: UpdatePost, title: String, content: String) -> UpdatePost {
+    UpdatePost {
+        title: Some(update.title.unwrap_or(title)),
+        content: Some(update.content.unwrap_or(content)),
+    }
+}
+
+/// Applies journaled writes left by a previous run, oldest first.
+pub async fn replay(db: &Pool<Postgres>) {
+    let Some(journal) = JOURNAL.get() else {
+        return;
+    };
+    let entries = match journal.entries().await {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!(error = %e, "Failed to read journal for replay");
+            return;
+        }
+    };
+    if entries.is_empty() {
+        return;
+    }
+
+    let (mut replayed, mut failed) = (0, Vec::new());
+    for (
+        post_id,
+        Merged {
+            user_id,
+            seqs,
+            update,
+            deleted,
+        },
+    ) in merge(entries)
+    {
+        let current: Option<(i64, String, String)> =
+            match sqlx::query_as("SELECT user_id, title, content FROM posts WHERE id = $1")
+                .bind(post_id)
+                .fetch_optional(db)
+                .await
+            {
+                Ok(current) => current,
+                Err(e) => {
+                    error!(post_id, error = %e, "Journal replay failed");
+                    failed.push(post_id);
+                    continue;
+                }
+            };
+        let owner = current.as_ref().map(|(owner, _, _)| *owner);
+        if owner == Some(user_id) && deleted {
+            if let Err(e) = flush_delete(db, post_id).await {
+                error!(post_id, error = %e, "Journal replay failed");
+                failed.push(post_id);
+                continue;
+            }
+            replayed += 1;
+        } else if let Some((_, title, content)) = current.filter(|_| owner == Some(user_id)) {
+            let update = fill_missing(update, title, content);
+            if let Err((_, e)) =
+                __update_post_from_cache(State(db.clone()), Path(post_id), Json(update)).await
+            {
+                error!(post_id, error = %e, "Journal replay failed");
+                failed.push(post_id);
+                continue;
+            }
+            replayed += 1;
+        } else if !deleted {
+            warn!(
+                post_id,
+                "Journaled post is gone or changed owner, dropping writes"
+            );
+        }
+        if let Err(e) = journal.remove(&seqs).await {
+            warn!(post_id, error = %e, "Failed to truncate journal after replay");
+        }
+    }
+
+    if failed.is_empty() {
+        info!(replayed, "Journal replayed");
+    } else {
+        error!(replayed, failed = ?failed, "Journal replay incomplete; entries kept for next start");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(seq: i64, post_id: i64, title: Option<&str>, content: Option<&str>) -> PendingWrite {
+        PendingWrite {
+            seq,
+            post_id,
+            user_id: 1,
+            title: title.map(str::to_string),
+            content: content.map(str::to_string),
+            deleted: false,
+        }
+    }
+
+    fn delete(seq: i64, post_id: i64) -> PendingWrite {
+        PendingWrite {
+            deleted: true,
+            ..update(seq, post_id, None, None)
+        }
+    }
+
+    #[test]
+    fn merges_writes_per_post_in_order() {
+        let merged = merge(vec![
+            update(1, 10, Some("a"), Some("first")),
+            update(2, 20, Some("other"), None),
+            update(3, 10, None, Some("second")),
+            update(4, 10, Some("b"), None),
+        ]);
+        let post = &merged[&10];
+        assert_eq!(post.seqs, vec![1, 3, 4]);
+        assert_eq!(post.update.title.as_deref(), Some("b"));
+        assert_eq!(post.update.content.as_deref(), Some("second"));
+        assert!(!post.deleted);
+        assert_eq!(merged[&20].update.content, None);
+    }
+
+    #[test]
+    fn partial_writes_keep_the_other_field() {
+        let mut merged = merge(vec![update(1, 10, Some("new title"), None)]);
+        let update = fill_missing(
+            merged.remove(&10).unwrap().update,
+            "old title".to_string(),
+            "body".to_string(),
+        );
+        assert_eq!(update.title.as_deref(), Some("new title"));
+        assert_eq!(update.content.as_deref(), Some("body"));
+    }
+
+    #[test]
+    fn delete_wins_over_surrounding_updates() {
+        let merged = merge(vec![
+            update(1, 10, Some("a"), None),
+            delete(2, 10),
+            update(3, 10, Some("b"), None),
+        ]);
+        assert!(merged[&10].deleted);
+        assert_eq!(merged[&10].seqs, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn skips_entries_from_another_user() {
+        let mut foreign = update(2, 10, Some("hijack"), None);
+        foreign.user_id = 2;
+        let merged = merge(vec![update(1, 10, Some("a"), None), foreign]);
+        assert_eq!(merged[&10].user_id, 1);
+        assert_eq!(merged[&10].update.title.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn reads_lines_written_before_deletes_were_journaled() {
+        let entry: PendingWrite = serde_json::from_str(
+            r#"{"seq":1,"post_id":10,"user_id":1,"title":"a","content":null}"#,
+        )
+        .unwrap();
+        assert!(!entry.deleted);
+    }
+
+    #[test]
+    fn flush_covers_matching_and_superseded_entries() {
+        let entries = vec![
+            update(1, 10, Some("a"), Some("old")),
+            update(2, 10, Some("b"), None),
+            update(3, 10, None, Some("new")),
+            update(4, 20, Some("b"), Some("new")),
+        ];
+        // seq 1의 title은 seq 2가, content는 seq 3이 덮어씀
+        assert_eq!(covered_by_flush(&entries, 10, "b", "new"), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn flush_keeps_entries_not_merged_yet() {
+        let entries = vec![
+            update(1, 10, Some("a"), None),
+            update(2, 10, Some("b"), None),
+            update(3, 10, Some("c"), None),
+        ];
+        // seq 3은 아직 캐시에 합쳐지지 않았음
+        assert_eq!(covered_by_flush(&entries, 10, "b", "body"), vec![1, 2]);
+        // 어떤 entry도 flush 값과 다르면 아무것도 지우지 않음
+        assert!(covered_by_flush(&entries, 10, "z", "body").is_empty());
+    }
+
+    #[test]
+    fn flush_never_covers_deletes() {
+        let entries = vec![update(1, 10, Some("a"), None), delete(2, 10)];
+        assert_eq!(covered_by_flush(&entries, 10, "a", "body"), vec![1]);
+    }
+
+    fn file_journal(path: PathBuf, entries: Vec<PendingWrite>) -> Journal {
+        let next_seq = entries.iter().map(|entry| entry.seq).max().unwrap_or(0) + 1;
+        Journal::File(FileJournal {
+            path,
+            state: Mutex::new(FileState { next_seq, entries }),
+        })
+    }
+
+    fn post_update(title: &str) -> UpdatePost {
+        UpdatePost {
+            title: Some(title.to_string()),
+            content: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn file_journal_removes_only_given_seqs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let journal = file_journal(path.clone(), Vec::new());
+
+        let first = journal
+            .append(10, 1, Write::Update(&post_update("a")))
+            .await
+            .unwrap();
+        let second = journal
+            .append(10, 1, Write::Update(&post_update("b")))
+            .await
+            .unwrap();
+        let third = journal.append(10, 1, Write::Delete).await.unwrap();
+        assert_eq!((first, second, third), (1, 2, 3));
+
+        // 거절된 seq 2만 빠지고 앞뒤 entry는 replay를 위해 남음
+        journal.remove(&[second]).await.unwrap();
+        let seqs: Vec<i64> = journal
+            .entries()
+            .await
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(seqs, vec![1, 3]);
+
+        let reloaded = read_file(&path).await.unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded[0].title.as_deref(), Some("a"));
+        assert!(reloaded[1].deleted);
+    }
+
+    #[tokio::test]
+    async fn file_journal_lists_one_post() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = file_journal(
+            dir.path().join("journal.jsonl"),
+            vec![
+                update(1, 10, Some("a"), None),
+                update(2, 20, Some("b"), None),
+                delete(3, 10),
+            ],
+        );
+        let seqs: Vec<i64> = journal
+            .entries_for(10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(seqs, vec![1, 3]);
+        assert!(journal.entries_for(30).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn file_journal_forgets_a_post() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = file_journal(dir.path().join("journal.jsonl"), Vec::new());
+        journal
+            .append(10, 1, Write::Update(&post_update("a")))
+            .await
+            .unwrap();
+        journal
+            .append(20, 1, Write::Update(&post_update("b")))
+            .await
+            .unwrap();
+        journal.append(10, 1, Write::Delete).await.unwrap();
+
+        journal.remove_post(10).await.unwrap();
+        let entries = journal.entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].post_id, 20);
+    }
+
+    #[tokio::test]
+    async fn skips_torn_last_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        std::fs::write(
+            &path,
+            "{\"seq\":1,\"post_id\":10,\"user_id\":1,\"title\":\"a\",\"content\":null}\n{\"seq\":2,\"po",
+        )
+        .unwrap();
+        let entries = read_file(&path).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].seq, 1);
+        assert!(
+            read_file(&dir.path().join("missing.jsonl"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -1,17 +1,31 @@
+mod breaker;
 mod cache;
-mod graph;
+pub mod cluster;
+pub mod dirty;
+pub mod embedder;
+mod export;
+pub mod graph;
 mod handlers;
+pub mod journal;
 mod models;
+pub mod query_cache;
+mod recommend;
+pub mod reembed;
+pub mod related;
+mod search;
+mod snippet;
+mod stats;
 mod utils;
 
-pub use cache::{callback, delete_callback, write_to_cache};
+pub use breaker::{CacheBreaker, CacheLayerState};
+pub use cache::{CACHE_KEY, callback, delete_callback, write_to_cache};
+pub use embedder::Embedder;
 pub use graph::{get_graph_data, get_related_post};
 pub use handlers::{
     __update_post_from_cache, create_post, delete_post, get_posts, list_posts, update_post,
 };
 pub use models::{
-    CreatePost, EmbeddingResponse, GraphData, GraphLink, GraphNode, Post, PostGraphData,
-    PostResponse, UpdatePost,
+    CreatePost, EmbeddingResponse, GraphData, GraphLink, GraphNode, Post, PostResponse, UpdatePost,
 };
 pub use utils::internal_error;
 
@@ -20,27 +34,41 @@ use axum::{
     middleware::{self},
 };
 
-use sqlx::{Postgres, pool::Pool};
+use crate::monitoring;
+use crate::state::AppState;
 
-use axum_redis_cache::CacheState;
-
-pub fn routes(cache_state: CacheState) -> Router<Pool<Postgres>> {
-    Router::new()
-        .merge(post_routes_auth())
-        .merge(post_routes_cache().layer(middleware::from_fn_with_state(
-            cache_state,
-            axum_redis_cache::middleware,
-        )))
+pub fn routes(cache_state: CacheLayerState) -> Router<AppState> {
+    Router::new().merge(post_routes_auth()).merge(
+        post_routes_cache()
+            .layer(middleware::from_fn(monitoring::mark_cache_miss))
+            .layer(middleware::from_fn_with_state(
+                cache_state,
+                breaker::cache_or_bypass,
+            ))
+            .layer(middleware::from_fn(related::refresh_related))
+            .layer(middleware::from_fn(monitoring::track_cache))
+            .layer(middleware::from_fn(journal::record)),
+    )
 }
 
-fn post_routes_auth() -> Router<Pool<Postgres>> {
+fn post_routes_auth() -> Router<AppState> {
     Router::new()
         .route("/posts", axum::routing::get(list_posts).post(create_post))
         .route("/posts/search", axum::routing::get(handlers::search_posts))
         .route("/posts/graph", axum::routing::get(get_graph_data))
+        .route(
+            "/posts/graph/stats",
+            axum::routing::get(stats::get_graph_stats),
+        )
+        .route("/clusters", axum::routing::get(cluster::list_clusters))
+        .route(
+            "/posts/:id/related",
+            axum::routing::get(recommend::get_related_posts),
+        )
+        .route("/posts/:id/graph", axum::routing::get(graph::get_ego_graph))
 }
 
-fn post_routes_cache() -> Router<Pool<Postgres>> {
+fn post_routes_cache() -> Router<AppState> {
     Router::new().route(
         "/posts/:id",
         axum::routing::get(get_posts)
@@ -14,13 +14,6 @@ pub struct Post {
     pub user_id: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct PostGraphData {
-    pub id: i64,
-    pub title: String,
-    pub embedding: Option<pgvector::Vector>,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct CreatePost {
     pub title: String,
@@ -44,10 +37,50 @@ pub struct PostResponse {
     pub related_posts: Vec<Post>,
 }
 
+// GET /posts/:id/related
+#[derive(Debug, Deserialize)]
+pub struct RelatedQuery {
+    pub limit: Option<i64>,
+    /// Minimum similarity (0.0 ~ 1.0); defaults to `similarity.min_similarity`.
+    pub min_score: Option<f64>,
+    /// Leave out posts this post already links to.
+    #[serde(default)]
+    pub exclude_linked: bool,
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ScoredPost {
+    pub id: i64,
+    pub title: String,
+    pub updated_at: DateTime<Utc>,
+    pub score: f64,
+    #[serde(skip)]
+    pub content: String,
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<RelatedExplanation>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedExplanation {
+    /// Key terms both posts use often, most shared first.
+    pub shared_terms: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphNode {
     pub id: String,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_label: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +90,101 @@ pub struct GraphLink {
     pub value: f32,
 }
 
+// GET /posts/graph
+#[derive(Debug, Deserialize)]
+pub struct GraphQuery {
+    #[serde(default)]
+    pub format: GraphFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Graphml,
+    Gexf,
+    Dot,
+    #[serde(rename = "json-ld")]
+    JsonLd,
+}
+
+// GET /posts/:id/graph
+#[derive(Debug, Deserialize)]
+pub struct EgoGraphQuery {
+    /// Number of hops from the post; defaults to 2.
+    pub depth: Option<u32>,
+    /// Maximum number of nodes, the post itself included; defaults to 50.
+    pub limit: Option<usize>,
+}
+
+// GET /posts/graph/stats
+#[derive(Debug, Deserialize)]
+pub struct GraphStatsQuery {
+    /// Number of posts listed under `central`; defaults to 20.
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphStats {
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Posts ranked by PageRank, most central first.
+    pub central: Vec<NodeCentrality>,
+    /// Posts without any neighbor above the similarity threshold.
+    pub orphans: Vec<GraphStatsNode>,
+    /// Posts linked to posts of other clusters.
+    pub bridges: Vec<BridgeNode>,
+    /// Connected components, largest first.
+    pub components: Vec<GraphComponent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeCentrality {
+    pub id: String,
+    pub name: String,
+    pub degree: usize,
+    pub pagerank: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphStatsNode {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeNode {
+    pub id: String,
+    pub name: String,
+    pub cluster_id: Option<i32>,
+    /// Other clusters this post has neighbors in.
+    pub connects: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphComponent {
+    pub size: usize,
+    pub post_ids: Vec<String>,
+}
+
+// GET /clusters
+#[derive(Debug, Clone, Serialize)]
+pub struct Cluster {
+    pub id: i32,
+    pub label: String,
+    /// Top TF-IDF terms, most characteristic first.
+    pub terms: Vec<String>,
+    pub size: usize,
+    pub posts: Vec<ClusterMember>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterMember {
+    pub id: i64,
+    pub title: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphData {
     pub nodes: Vec<GraphNode>,
@@ -64,25 +192,88 @@ pub struct GraphData {
 }
 
 //for query embedding
-#[derive(Deserialize)]
+#[derive(Default, Deserialize)]
 pub struct SearchQuery {
+    /// Words, phrases and filters; see `search::ParsedQuery` for the syntax.
+    #[serde(default)]
     pub q: String,
+    /// Same as `created:` in `q`, e.g. `>=2025-01-01`.
+    pub created: Option<String>,
+    /// Same as `updated:` in `q`.
+    pub updated: Option<String>,
+    /// Title (folder) prefix, same as `in:`.
+    pub prefix: Option<String>,
+    /// Comma-separated tags, all required.
+    pub tags: Option<String>,
+    /// Only posts that have no embedding yet.
+    #[serde(default)]
+    pub missing_embedding: bool,
 }
 
-// Define a struct for the embedding request payload
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchedField {
+    Title,
+    Content,
+}
+
+/// `[start, end)` of a highlighted match in UTF-16 code units.
+#[derive(Debug, Clone, Serialize)]
+pub struct HighlightRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, FromRow)]
+pub struct SearchRow {
+    #[sqlx(flatten)]
+    pub post: Post,
+    pub score: f64,
+}
+
+// GET /posts/search
 #[derive(Debug, Serialize)]
-pub struct EmbeddingRequest {
-    pub text: String,
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub post: Post,
+    /// `1 - cosine distance / 2` to the query; 0 for posts without an embedding.
+    pub score: f64,
+    pub matched_field: MatchedField,
+    /// Title, or the best matching window of plain-text content.
+    pub snippet: String,
+    /// Query terms found in `snippet`; empty when the post matched by meaning
+    /// only, in which case `snippet` is the start of its content.
+    pub highlights: Vec<HighlightRange>,
 }
 
 // Define a struct for the embedding request payload
 #[derive(Debug, Serialize)]
 pub struct QueryRequest {
     pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+// /embed with a list of texts (one embedding per text, same order)
+#[derive(Debug, Serialize)]
+pub struct BatchEmbeddingRequest {
+    pub texts: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 // Define a struct for the embedding response payload
 #[derive(Debug, Deserialize)]
 pub struct EmbeddingResponse {
     pub embedding: Vec<f32>,
+    /// Model that produced the embedding; older embedding APIs leave it out.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchEmbeddingResponse {
+    pub embeddings: Vec<Vec<f32>>,
+    #[serde(default)]
+    pub model: Option<String>,
 }
@@ -0,0 +1,135 @@
+// src/posts/query_cache.rs
+
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+use metrics::counter;
+use once_cell::sync::OnceCell;
+use redis::AsyncCommands;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use super::utils::SharedConnection;
+use crate::config::EmbeddingConfig;
+
+// embeddings:query:{model}:{sha256(query)} -> embedding (JSON)
+const KEY_PREFIX: &str = "embeddings:query";
+
+static CACHE: OnceCell<QueryCache> = OnceCell::new();
+
+/// Search query embeddings, keyed by model id so a model switch never mixes
+/// vectors from two spaces.
+///
+/// A per-instance LRU sits in front of Redis; Redis is shared by every
+/// instance and survives restarts.
+struct QueryCache {
+    memory: Mutex<LruCache<(String, String), Vec<f32>>>,
+    redis: SharedConnection,
+    ttl_secs: u64,
+}
+
+pub fn init(client: redis::Client, config: &EmbeddingConfig) {
+    let capacity = NonZeroUsize::new(config.query_cache_size).unwrap_or(NonZeroUsize::MIN);
+    let _ = CACHE.set(QueryCache {
+        memory: Mutex::new(LruCache::new(capacity)),
+        redis: SharedConnection::new(client),
+        ttl_secs: config.query_cache_ttl_secs,
+    });
+}
+
+fn redis_key(model: &str, query: &str) -> String {
+    format!(
+        "{KEY_PREFIX}:{model}:{:x}",
+        Sha256::digest(query.as_bytes())
+    )
+}
+
+/// Cached embedding of `query` under `model`, from memory or Redis.
+pub async fn get(model: &str, query: &str) -> Option<Vec<f32>> {
+    let cache = CACHE.get()?;
+    let key = (model.to_string(), query.to_string());
+    if let Some(embedding) = cache.memory.lock().unwrap().get(&key) {
+        counter!("query_embedding_cache_total", "result" => "memory").increment(1);
+        return Some(embedding.clone());
+    }
+
+    let raw: Option<String> = match cache.redis.get().await {
+        Ok(mut conn) => match conn.get(redis_key(model, query)).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!(error = %e, "Failed to read cached query embedding");
+                cache.redis.reset().await;
+                None
+            }
+        },
+        Err(_) => None,
+    };
+    let Some(embedding) = raw.and_then(|raw| serde_json::from_str::<Vec<f32>>(&raw).ok()) else {
+        counter!("query_embedding_cache_total", "result" => "miss").increment(1);
+        return None;
+    };
+    counter!("query_embedding_cache_total", "result" => "redis").increment(1);
+    cache.memory.lock().unwrap().put(key, embedding.clone());
+    Some(embedding)
+}
+
+/// Remembers the embedding the API returned for `query` under `model`.
+pub async fn store(model: &str, query: &str, embedding: &[f32]) {
+    let Some(cache) = CACHE.get() else {
+        return;
+    };
+    cache
+        .memory
+        .lock()
+        .unwrap()
+        .put((model.to_string(), query.to_string()), embedding.to_vec());
+
+    let Ok(raw) = serde_json::to_string(embedding) else {
+        return;
+    };
+    let result = match cache.redis.get().await {
+        Ok(mut conn) => {
+            conn.set_ex::<_, _, ()>(redis_key(model, query), raw, cache.ttl_secs)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(error = %e, "Failed to store query embedding");
+        cache.redis.reset().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redis_key_hashes_query_per_model() {
+        let key = redis_key("bge-m3", "러스트 비동기");
+        let (prefix, hash) = key.rsplit_once(':').unwrap();
+        assert_eq!(prefix, "embeddings:query:bge-m3");
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        assert_eq!(key, redis_key("bge-m3", "러스트 비동기"));
+        assert_ne!(key, redis_key("bge-m3", "러스트"));
+        assert_ne!(key, redis_key("e5-large", "러스트 비동기"));
+    }
+
+    #[tokio::test]
+    async fn memory_cache_keeps_models_apart() {
+        // Redis에 연결할 수 없어도 메모리 LRU는 동작해야 함
+        let config = EmbeddingConfig {
+            query_cache_size: 4,
+            query_cache_ttl_secs: 60,
+            ..EmbeddingConfig::default()
+        };
+        init(redis::Client::open("redis://127.0.0.1:1").unwrap(), &config);
+
+        store("bge-m3", "graph", &[0.5, 0.25]).await;
+        assert_eq!(get("bge-m3", "graph").await, Some(vec![0.5, 0.25]));
+        assert_eq!(get("e5-large", "graph").await, None);
+    }
+}
@@ -0,0 +1,216 @@
+// src/posts/recommend.rs
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use jwt_authorizer::JwtClaims;
+use sqlx::{Postgres, pool::Pool};
+
+use super::models::{Post, RelatedExplanation, RelatedQuery, ScoredPost};
+use super::utils::internal_error;
+use crate::auth::UserClaims;
+use crate::config::Config;
+
+const MAX_LIMIT: i64 = 50;
+const MAX_SHARED_TERMS: usize = 5;
+// 설명에서 제외할 흔한 영어 단어
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "that", "this", "from", "are", "was", "were", "have", "has",
+    "not", "but", "you", "your", "our", "its", "into", "can", "will", "what", "when", "which",
+    "how", "all", "any", "about", "there", "their", "they", "them", "then", "than", "also",
+];
+
+/// `GET /posts/:id/related`: similar posts with similarity scores.
+///
+/// `score` is `1 - cosine distance / 2`, the same scale as graph link values
+/// and `similarity.min_similarity`.
+pub async fn get_related_posts(
+    State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+    Path(id): Path<i64>,
+    Query(query): Query<RelatedQuery>,
+) -> Result<Json<Vec<ScoredPost>>, (StatusCode, String)> {
+    let limit = query
+        .limit
+        .unwrap_or(config.similarity.related_limit)
+        .clamp(1, MAX_LIMIT);
+    let min_score = query.min_score.unwrap_or(config.similarity.min_similarity);
+    if !(0.0..=1.0).contains(&min_score) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "min_score must be between 0.0 and 1.0".to_string(),
+        ));
+    }
+
+    let post = sqlx::query_as::<_, Post>("SELECT * FROM posts WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user.sub)
+        .fetch_optional(&db)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Post not found".to_string()))?;
+    let Some(embedding) = &post.embedding else {
+        // embedding 계산 전
+        return Ok(Json(Vec::new()));
+    };
+
+    let excluded: Vec<i64> = if query.exclude_linked {
+        linked_post_ids(&post.content)
+    } else {
+        Vec::new()
+    };
+
+    let mut related = sqlx::query_as::<_, ScoredPost>(
+        r#"
+        SELECT id, title, updated_at, content,
+            1 - (embedding <=> $3) / 2 AS score
+        FROM posts
+        WHERE user_id = $1
+        AND id != $2
+        AND id <> ALL($4)
+        AND embedding IS NOT NULL
+        AND embedding <=> $3 <= $5
+        ORDER BY embedding <=> $3
+        LIMIT $6
+        "#,
+    )
+    .bind(user.sub)
+    .bind(id)
+    .bind(embedding)
+    .bind(&excluded)
+    .bind(2.0 * (1.0 - min_score))
+    .bind(limit)
+    .fetch_all(&db)
+    .await
+    .map_err(internal_error)?;
+
+    if query.explain {
+        let source = term_counts(&format!("{} {}", post.title, post.content));
+        for candidate in &mut related {
+            let other = term_counts(&format!("{} {}", candidate.title, candidate.content));
+            candidate.explanation = Some(RelatedExplanation {
+                shared_terms: shared_terms(&source, &other),
+            });
+        }
+    }
+
+    Ok(Json(related))
+}
+
+/// Ids of posts referenced as `/posts/<id>` links in the (HTML) content.
+fn linked_post_ids(content: &str) -> Vec<i64> {
+    let mut ids = HashSet::new();
+    for (start, _) in content.match_indices("/posts/") {
+        let digits: String = content[start + "/posts/".len()..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        if let Ok(id) = digits.parse() {
+            ids.insert(id);
+        }
+    }
+    ids.into_iter().collect()
+}
+
+pub(super) fn term_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for term in strip_tags(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|term| term.chars().count() >= 2 && !term.chars().all(|c| c.is_ascii_digit()))
+        .filter(|term| !STOPWORDS.contains(&term.as_str()))
+    {
+        *counts.entry(term).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn shared_terms(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> Vec<String> {
+    let mut shared: Vec<(&String, usize)> = a
+        .iter()
+        .filter_map(|(term, count)| b.get(term).map(|other| (term, (*count).min(*other))))
+        .collect();
+    shared.sort_by(|(ta, ca), (tb, cb)| cb.cmp(ca).then_with(|| ta.cmp(tb)));
+    shared
+        .into_iter()
+        .take(MAX_SHARED_TERMS)
+        .map(|(term, _)| term.clone())
+        .collect()
+}
+
+// 에디터가 저장하는 HTML 태그 제거
+pub(super) fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(pairs: &[(&str, usize)]) -> HashMap<String, usize> {
+        pairs
+            .iter()
+            .map(|(term, count)| (term.to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn linked_post_ids_collects_unique_ids() {
+        let mut ids = linked_post_ids(
+            r#"<a href="/posts/12">a</a> <a href="https://x.dev/posts/7?x=1">b</a> /posts/12 /posts/ /posts/abc"#,
+        );
+        ids.sort();
+        assert_eq!(ids, vec![7, 12]);
+    }
+
+    #[test]
+    fn strip_tags_separates_text_nodes() {
+        assert_eq!(strip_tags("<p>rust</p><p>axum</p>"), " rust  axum ");
+        assert_eq!(strip_tags("a > b"), "a > b");
+    }
+
+    #[test]
+    fn term_counts_skips_short_numeric_and_stopwords() {
+        let counts = term_counts("<p>The Rust and rust 2024 a 그래프</p><b>그래프</b>");
+        assert_eq!(counts.get("rust"), Some(&2));
+        assert_eq!(counts.get("그래프"), Some(&2));
+        assert!(!counts.contains_key("the"));
+        assert!(!counts.contains_key("and"));
+        assert!(!counts.contains_key("2024"));
+        assert!(!counts.contains_key("a"));
+        assert!(!counts.contains_key("p"));
+    }
+
+    #[test]
+    fn shared_terms_orders_by_common_count_then_term() {
+        let a = counts(&[("rust", 5), ("axum", 2), ("redis", 2), ("only", 9)]);
+        let b = counts(&[("rust", 1), ("axum", 3), ("redis", 4)]);
+        assert_eq!(shared_terms(&a, &b), vec!["axum", "redis", "rust"]);
+    }
+
+    #[test]
+    fn shared_terms_is_capped() {
+        let terms: Vec<(String, usize)> = (0..MAX_SHARED_TERMS + 3)
+            .map(|i| (format!("term{i}"), 1))
+            .collect();
+        let a: HashMap<String, usize> = terms.iter().cloned().collect();
+        assert_eq!(shared_terms(&a, &a).len(), MAX_SHARED_TERMS);
+    }
+}
@@ -0,0 +1,813 @@
+// src/posts/reembed.rs
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, future::join_all, stream};
+use metrics::counter;
+use pgvector::Vector;
+use serde::Serialize;
+use sqlx::{Postgres, pool::Pool};
+use tracing::{error, info, warn};
+
+use super::models::EmbeddingResponse;
+use super::utils::EmbeddingApiError;
+use super::{cluster, embedder, graph, related};
+use crate::config::EmbeddingConfig;
+
+// 남은 post가 없거나 오류가 난 뒤 다시 확인하기까지 대기
+const IDLE_POLL: Duration = Duration::from_secs(30);
+const PROBE_TEXT: &str = "embedding dimension probe";
+// 같은 내용으로 이만큼 실패한 post는 내용이 바뀔 때까지 건너뜀
+const MAX_ATTEMPTS: i32 = 5;
+
+// 프로세스당 worker 하나
+static WORKER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReembedError {
+    #[error("re-embedding into {0} is already running")]
+    AlreadyRunning(String),
+    #[error("{0} is already the active embedding model")]
+    AlreadyActive(String),
+    #[error("no re-embedding is running")]
+    NotRunning,
+    #[error("{0} posts still need re-embedding")]
+    Incomplete(i64),
+    #[error("embedding API does not report which model it used")]
+    Unsupported,
+    #[error("embedding API error: {0}")]
+    Embedding(#[from] EmbeddingApiError),
+    #[error("database error: {0}")]
+    Db(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct Target {
+    model: String,
+    dimension: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct ModelRow {
+    model: String,
+    dimension: i32,
+    status: String,
+    failed: i64,
+    last_error: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+/// State of the latest model switch, for `GET /admin/embeddings/migration`.
+#[derive(Debug, Serialize)]
+pub struct Progress {
+    pub model: String,
+    pub dimension: i32,
+    /// `migrating`, `active` (cut over) or `cancelled`.
+    pub status: String,
+    /// Posts with an embedding, i.e. the posts that need one from the new model.
+    pub total: i64,
+    pub done: i64,
+    pub remaining: i64,
+    /// Posts skipped after failing `MAX_ATTEMPTS` times; retried once edited.
+    pub failed: i64,
+    pub last_error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Model whose embeddings are in `posts.embedding`; new embeddings and query
+/// embeddings must come from it.
+/// `default_model` (`embedding.model`) applies until a switch has completed.
+pub async fn active_model(db: &Pool<Postgres>, default_model: &str) -> Result<String, sqlx::Error> {
+    let model: Option<String> =
+        sqlx::query_scalar("SELECT model FROM embedding_models WHERE status = 'active'")
+            .fetch_optional(db)
+            .await?;
+    Ok(model.unwrap_or_else(|| default_model.to_string()))
+}
+
+/// Dimension of the stored embeddings of the active `model`; `None` while no
+/// post has one yet.
+pub async fn active_dimension(
+    db: &Pool<Postgres>,
+    model: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    // 전환을 마친 모델은 embedding_models에, 기본 모델은 기존 embedding에서
+    sqlx::query_scalar(
+        r#"SELECT dimension FROM embedding_models WHERE model = $1 AND status = 'active'
+            UNION ALL
+            (SELECT embedding_dim FROM posts
+                WHERE embedding_model = $1 AND embedding_dim IS NOT NULL LIMIT 1)
+            LIMIT 1"#,
+    )
+    .bind(model)
+    .fetch_optional(db)
+    .await
+}
+
+// 아직 embedding이 하나도 없으면 직접 embedding해서 차원 확인
+async fn active_target(db: &Pool<Postgres>, model: &str) -> Result<Target, ReembedError> {
+    let dimension = match active_dimension(db, model).await? {
+        Some(dimension) => dimension,
+        None => embedder::get()
+            .embed(PROBE_TEXT.to_string(), Some(model.to_string()))
+            .await?
+            .embedding
+            .len() as i32,
+    };
+    Ok(Target {
+        model: model.to_string(),
+        dimension,
+    })
+}
+
+async fn current_target(db: &Pool<Postgres>) -> Result<Option<Target>, sqlx::Error> {
+    sqlx::query_as::<_, Target>(
+        "SELECT model, dimension FROM embedding_models WHERE status = 'migrating'",
+    )
+    .fetch_optional(db)
+    .await
+}
+
+/// Starts filling `embedding_next` with embeddings from `model`.
+///
+/// Search and related posts keep using `embedding` until [`cutover`].
+pub async fn start(
+    db: &Pool<Postgres>,
+    config: &'static EmbeddingConfig,
+    model: &str,
+) -> Result<Progress, ReembedError> {
+    if active_model(db, &config.model).await? == model {
+        return Err(ReembedError::AlreadyActive(model.to_string()));
+    }
+    if let Some(target) = current_target(db).await? {
+        return Err(ReembedError::AlreadyRunning(target.model));
+    }
+
+    // 모델의 차원은 직접 embedding해서 확인
+    let probe = embedder::get()
+        .embed(PROBE_TEXT.to_string(), Some(model.to_string()))
+        .await?;
+    if probe.model.as_deref() != Some(model) {
+        return Err(ReembedError::Unsupported);
+    }
+    let dimension = probe.embedding.len() as i32;
+
+    let mut tx = db.begin().await?;
+    sqlx::query(
+        r#"UPDATE posts
+            SET embedding_next = NULL, embedding_next_model = NULL
+            WHERE embedding_next_model IS NOT NULL"#,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO embedding_models (model, dimension, status, started_at)
+        VALUES ($1, $2, 'migrating', CURRENT_TIMESTAMP)
+        ON CONFLICT (model) DO UPDATE
+        SET dimension = EXCLUDED.dimension,
+            status = 'migrating',
+            failed = 0,
+            last_error = NULL,
+            started_at = EXCLUDED.started_at,
+            finished_at = NULL
+        "#,
+    )
+    .bind(model)
+    .bind(dimension)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM reembed_failures WHERE model = $1")
+        .bind(model)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    info!(model, dimension, "Re-embedding started");
+    spawn_worker(db.clone(), config);
+    progress(db).await?.ok_or(ReembedError::NotRunning)
+}
+
+/// Progress of the running or most recent model switch.
+pub async fn progress(db: &Pool<Postgres>) -> Result<Option<Progress>, sqlx::Error> {
+    let Some(row) = sqlx::query_as::<_, ModelRow>(
+        r#"SELECT model, dimension, status, failed, last_error, started_at, finished_at
+            FROM embedding_models
+            WHERE started_at IS NOT NULL
+            ORDER BY started_at DESC
+            LIMIT 1"#,
+    )
+    .fetch_optional(db)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    // cut over 후에는 새 모델이 embedding 컬럼에 있음
+    let column = if row.status == "active" {
+        "embedding_model"
+    } else {
+        "embedding_next_model"
+    };
+    let (total, done): (i64, i64) = sqlx::query_as(&format!(
+        r#"SELECT COUNT(*), COUNT(*) FILTER (WHERE {column} = $1)
+            FROM posts
+            WHERE embedding IS NOT NULL"#
+    ))
+    .bind(&row.model)
+    .fetch_one(db)
+    .await?;
+
+    Ok(Some(Progress {
+        model: row.model,
+        dimension: row.dimension,
+        status: row.status,
+        total,
+        done,
+        remaining: total - done,
+        failed: row.failed,
+        last_error: row.last_error,
+        started_at: row.started_at,
+        finished_at: row.finished_at,
+    }))
+}
+
+/// Stops the running switch and drops what it embedded so far.
+pub async fn cancel(db: &Pool<Postgres>) -> Result<Progress, ReembedError> {
+    let mut tx = db.begin().await?;
+    let model: Option<String> = sqlx::query_scalar(
+        r#"UPDATE embedding_models
+            SET status = 'cancelled', finished_at = CURRENT_TIMESTAMP
+            WHERE status = 'migrating'
+            RETURNING model"#,
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(model) = model else {
+        return Err(ReembedError::NotRunning);
+    };
+    sqlx::query(
+        r#"UPDATE posts
+            SET embedding_next = NULL, embedding_next_model = NULL
+            WHERE embedding_next_model IS NOT NULL"#,
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    info!(model, "Re-embedding cancelled");
+    progress(db).await?.ok_or(ReembedError::NotRunning)
+}
+
+/// Swaps `embedding_next` in as `embedding` in one transaction.
+///
+/// Queries on `posts` wait while the HNSW index and the neighbor edges are
+/// rebuilt, and see either the old or the new model, never a mix. Refused
+/// while any embedded post still lacks a new-model embedding.
+pub async fn cutover(db: &Pool<Postgres>) -> Result<Progress, ReembedError> {
+    let mut tx = db.begin().await?;
+    let target = sqlx::query_as::<_, Target>(
+        "SELECT model, dimension FROM embedding_models WHERE status = 'migrating' FOR UPDATE",
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ReembedError::NotRunning)?;
+
+    sqlx::query("LOCK TABLE posts IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+    let remaining: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM posts
+            WHERE embedding IS NOT NULL
+            AND embedding_next_model IS DISTINCT FROM $1"#,
+    )
+    .bind(&target.model)
+    .fetch_one(&mut *tx)
+    .await?;
+    if remaining > 0 {
+        return Err(ReembedError::Incomplete(remaining));
+    }
+
+    // 차원이 바뀔 수 있으므로 컬럼을 통째로 교체하고 index를 다시 만듦
+    for statement in [
+        "DROP INDEX IF EXISTS posts_embedding_idx".to_string(),
+        "ALTER TABLE posts DROP COLUMN embedding".to_string(),
+        "ALTER TABLE posts RENAME COLUMN embedding_next TO embedding".to_string(),
+        format!(
+            "ALTER TABLE posts ALTER COLUMN embedding TYPE vector({})",
+            target.dimension
+        ),
+        r#"UPDATE posts
+            SET embedding_model = CASE WHEN embedding IS NULL THEN NULL ELSE embedding_next_model END,
+                embedding_dim = CASE WHEN embedding IS NULL THEN NULL ELSE vector_dims(embedding) END,
+                embedding_next_model = NULL"#
+            .to_string(),
+        "ALTER TABLE posts ADD COLUMN embedding_next vector".to_string(),
+        "CREATE INDEX posts_embedding_idx ON posts USING hnsw (embedding vector_cosine_ops)"
+            .to_string(),
+        "UPDATE embedding_models SET status = 'retired' WHERE status = 'active'".to_string(),
+    ] {
+        sqlx::query(&statement).execute(&mut *tx).await?;
+    }
+    sqlx::query(
+        r#"UPDATE embedding_models
+            SET status = 'active', finished_at = CURRENT_TIMESTAMP
+            WHERE model = $1"#,
+    )
+    .bind(&target.model)
+    .execute(&mut *tx)
+    .await?;
+    graph::rebuild_edges(&mut tx, None).await?;
+    tx.commit().await?;
+
+    info!(
+        model = target.model,
+        dimension = target.dimension,
+        "Embedding model cut over"
+    );
+    // 유사도가 모두 바뀌었으므로 파생 데이터 전부 무효화
+    related::clear().await;
+    let users: Vec<i64> = sqlx::query_scalar("SELECT DISTINCT user_id FROM posts")
+        .fetch_all(db)
+        .await?;
+    for user_id in users {
+        graph::invalidate_graph(user_id).await;
+        cluster::schedule(user_id);
+    }
+    progress(db).await?.ok_or(ReembedError::NotRunning)
+}
+
+/// Starts the background re-embedding worker if a switch is in progress
+/// (after [`start`], or on startup to resume one).
+pub fn spawn_worker(db: Pool<Postgres>, config: &'static EmbeddingConfig) {
+    if WORKER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tokio::spawn(async move {
+        run(&db, config.reembed_batch_size).await;
+        WORKER_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+async fn run(db: &Pool<Postgres>, batch_size: i64) {
+    let mut cursor = 0;
+    loop {
+        let target = match current_target(db).await {
+            Ok(Some(target)) => target,
+            // cut over 또는 취소됨
+            Ok(None) => return,
+            Err(e) => {
+                error!(error = %e, "Failed to read re-embedding state");
+                tokio::time::sleep(IDLE_POLL).await;
+                continue;
+            }
+        };
+
+        match reembed_batch(db, &target, cursor, batch_size).await {
+            Ok(Some(last_id)) => cursor = last_id,
+            Ok(None) => {
+                // 한 바퀴 끝. 그 사이 수정되었거나 실패한 post는 다음 바퀴에서 처리
+                cursor = 0;
+                tokio::time::sleep(IDLE_POLL).await;
+            }
+            Err(e) => {
+                error!(model = target.model, error = %e, "Re-embedding batch failed");
+                tokio::time::sleep(IDLE_POLL).await;
+            }
+        }
+    }
+}
+
+/// Embeds the next `limit` posts after `cursor` with the target model;
+/// returns the last id processed, or `None` once the pass is complete.
+///
+/// No lock is held while the embedding API is called: a result is only stored
+/// if the post's content is still what was embedded, so concurrent saves never
+/// wait on it and are never overwritten.
+async fn reembed_batch(
+    db: &Pool<Postgres>,
+    target: &Target,
+    cursor: i64,
+    limit: i64,
+) -> Result<Option<i64>, sqlx::Error> {
+    let posts = sqlx::query_as::<_, (i64, String)>(
+        r#"SELECT p.id, p.content FROM posts p
+            WHERE p.embedding IS NOT NULL
+            AND p.embedding_next_model IS DISTINCT FROM $1
+            AND p.id > $2
+            AND NOT EXISTS (
+                SELECT 1 FROM reembed_failures f
+                WHERE f.post_id = p.id
+                AND f.model = $1
+                AND f.attempts >= $4
+                AND f.content_hash = md5(p.content)
+            )
+            ORDER BY p.id
+            LIMIT $3"#,
+    )
+    .bind(&target.model)
+    .bind(cursor)
+    .bind(limit)
+    .bind(MAX_ATTEMPTS)
+    .fetch_all(db)
+    .await?;
+    let Some(&(last_id, _)) = posts.last() else {
+        return Ok(None);
+    };
+
+    // 한꺼번에 요청해서 embedder가 batch 하나로 묶도록
+    let embedder = embedder::get();
+    let results = join_all(posts.into_iter().map(|(id, content)| {
+        let embedder = embedder.clone();
+        let model = target.model.clone();
+        async move {
+            let result = embedder.embed(content.clone(), Some(model)).await;
+            (id, content, result)
+        }
+    }))
+    .await;
+
+    let (mut given_up, mut last_error) = (0i64, None);
+    for (id, content, result) in results {
+        match check_embedding(target, result) {
+            Ok(embedding) => {
+                let stored = sqlx::query(
+                    r#"UPDATE posts SET embedding_next = $1, embedding_next_model = $2
+                        WHERE id = $3 AND content = $4"#,
+                )
+                .bind(Vector::from(embedding))
+                .bind(&target.model)
+                .bind(id)
+                .bind(&content)
+                .execute(db)
+                .await?;
+                if stored.rows_affected() == 0 {
+                    // 그 사이 수정되었거나 삭제됨. 수정된 post는 다음 바퀴에서 처리
+                    counter!("reembed_posts_total", "result" => "skipped").increment(1);
+                    continue;
+                }
+                sqlx::query("DELETE FROM reembed_failures WHERE post_id = $1 AND model = $2")
+                    .bind(id)
+                    .bind(&target.model)
+                    .execute(db)
+                    .await?;
+                counter!("reembed_posts_total", "result" => "ok").increment(1);
+            }
+            Err(e) => {
+                warn!(post_id = id, model = target.model, error = %e, "Failed to re-embed post");
+                counter!("reembed_posts_total", "result" => "failed").increment(1);
+                // 내용이 바뀌었으면 횟수를 처음부터 다시 셈
+                let attempts: i32 = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO reembed_failures (post_id, model, content_hash, attempts, last_error)
+                    VALUES ($1, $2, md5($3), 1, $4)
+                    ON CONFLICT (post_id, model) DO UPDATE
+                    SET attempts = CASE
+                            WHEN reembed_failures.content_hash = EXCLUDED.content_hash
+                            THEN reembed_failures.attempts + 1
+                            ELSE 1
+                        END,
+                        content_hash = EXCLUDED.content_hash,
+                        last_error = EXCLUDED.last_error
+                    RETURNING attempts
+                    "#,
+                )
+                .bind(id)
+                .bind(&target.model)
+                .bind(&content)
+                .bind(&e)
+                .fetch_one(db)
+                .await?;
+                if attempts == MAX_ATTEMPTS {
+                    error!(
+                        post_id = id,
+                        model = target.model,
+                        attempts,
+                        "Giving up re-embedding post"
+                    );
+                    given_up += 1;
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if last_error.is_some() {
+        sqlx::query(
+            "UPDATE embedding_models SET failed = failed + $1, last_error = $2 WHERE model = $3",
+        )
+        .bind(given_up)
+        .bind(last_error)
+        .bind(&target.model)
+        .execute(db)
+        .await?;
+    }
+    Ok(Some(last_id))
+}
+
+// API 응답이 target 모델의 embedding인지 확인
+fn check_embedding(
+    target: &Target,
+    result: Result<EmbeddingResponse, EmbeddingApiError>,
+) -> Result<Vec<f32>, String> {
+    match result {
+        Ok(data) if data.embedding.len() as i32 != target.dimension => Err(format!(
+            "expected {} dimensions, got {}",
+            target.dimension,
+            data.embedding.len()
+        )),
+        Ok(data) if data.model.as_deref().is_some_and(|m| m != target.model) => Err(format!(
+            "embedding API answered with model {:?}",
+            data.model
+        )),
+        Ok(data) => Ok(data.embedding),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Options of the `reembed` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReembedOptions {
+    pub user_id: Option<i64>,
+    /// Only posts without an embedding from the active model.
+    pub only_missing: bool,
+    /// Embedding API calls in flight at once.
+    pub concurrency: usize,
+    /// Count what would be re-embedded without calling the API or writing.
+    pub dry_run: bool,
+    /// Ignore the checkpoint of an earlier, interrupted run.
+    pub restart: bool,
+}
+
+/// Outcome of a `reembed` run, including what a resumed run had done before.
+#[derive(Debug, Default)]
+pub struct ReembedReport {
+    pub model: String,
+    pub selected: i64,
+    pub done: i64,
+    pub failed: i64,
+    /// Posts edited while they were being embedded; their save re-embeds them.
+    pub skipped: i64,
+}
+
+impl ReembedOptions {
+    // 같은 옵션으로 다시 실행하면 checkpoint부터 이어서 진행
+    fn run_key(&self, model: &str) -> String {
+        let user = self
+            .user_id
+            .map_or_else(|| "all".to_string(), |id| id.to_string());
+        format!(
+            "model={model};user={user};only_missing={}",
+            self.only_missing
+        )
+    }
+}
+
+const SELECT_FOR_REEMBED: &str = r#"
+    FROM posts
+    WHERE ($1::BIGINT IS NULL OR user_id = $1)
+    AND (NOT $2 OR embedding IS NULL OR embedding_model IS DISTINCT FROM $3)
+    AND id > $4
+"#;
+
+/// Re-embeds posts in place with the active model (`neural_notes_axum reembed`).
+///
+/// Progress is checkpointed after every batch, so rerunning the same command
+/// after an interruption continues where it stopped. An embedding is only
+/// written if the post's content did not change while it was computed, and
+/// embeddings of another model or dimension are counted as failed.
+pub async fn run_command(
+    db: &Pool<Postgres>,
+    config: &EmbeddingConfig,
+    options: &ReembedOptions,
+) -> Result<ReembedReport, ReembedError> {
+    let model = active_model(db, &config.model).await?;
+    let run_key = options.run_key(&model);
+    let batch_size = config.reembed_batch_size.max(options.concurrency as i64);
+
+    if options.restart {
+        sqlx::query("DELETE FROM reembed_checkpoints WHERE run_key = $1")
+            .bind(&run_key)
+            .execute(db)
+            .await?;
+    }
+    let (mut cursor, mut done, mut failed, mut skipped) =
+        sqlx::query_as::<_, (i64, i64, i64, i64)>(
+            "SELECT last_id, done, failed, skipped FROM reembed_checkpoints WHERE run_key = $1",
+        )
+        .bind(&run_key)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or((0, 0, 0, 0));
+    if cursor > 0 {
+        info!(
+            run_key,
+            last_id = cursor,
+            done,
+            failed,
+            skipped,
+            "Resuming re-embed"
+        );
+    }
+
+    let remaining: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {SELECT_FOR_REEMBED}"))
+        .bind(options.user_id)
+        .bind(options.only_missing)
+        .bind(&model)
+        .bind(cursor)
+        .fetch_one(db)
+        .await?;
+    let mut report = ReembedReport {
+        model: model.clone(),
+        selected: done + failed + skipped + remaining,
+        done,
+        failed,
+        skipped,
+    };
+    if options.dry_run {
+        info!(model, remaining, "Dry run, nothing re-embedded");
+        return Ok(report);
+    }
+
+    let target = active_target(db, &model).await?;
+    let embedder = embedder::get();
+    loop {
+        let posts = sqlx::query_as::<_, (i64, String)>(&format!(
+            "SELECT id, content {SELECT_FOR_REEMBED} ORDER BY id LIMIT $5"
+        ))
+        .bind(options.user_id)
+        .bind(options.only_missing)
+        .bind(&model)
+        .bind(cursor)
+        .bind(batch_size)
+        .fetch_all(db)
+        .await?;
+        let Some(&(last_id, _)) = posts.last() else {
+            break;
+        };
+
+        let embedded: Vec<(i64, String, Result<Vec<f32>, String>)> = stream::iter(posts)
+            .map(|(id, content)| {
+                let (embedder, target) = (embedder.clone(), &target);
+                async move {
+                    let result = embedder
+                        .embed(content.clone(), Some(target.model.clone()))
+                        .await;
+                    // 다른 모델이나 차원의 embedding은 쓰지 않고 실패로 셈
+                    (id, content, check_embedding(target, result))
+                }
+            })
+            .buffer_unordered(options.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut tx = db.begin().await?;
+        for (id, content, result) in embedded {
+            match result {
+                Ok(embedding) => {
+                    let dimension = embedding.len() as i32;
+                    // 내용이 그대로이므로 진행 중인 모델 전환의 embedding_next는 유효함
+                    let updated = sqlx::query(
+                        r#"UPDATE posts
+                            SET embedding = $1, embedding_model = $2, embedding_dim = $3
+                            WHERE id = $4 AND content = $5"#,
+                    )
+                    .bind(Vector::from(embedding))
+                    .bind(&model)
+                    .bind(dimension)
+                    .bind(id)
+                    .bind(&content)
+                    .execute(&mut *tx)
+                    .await?;
+                    // 그 사이 수정된 post는 저장 경로가 새 내용으로 embedding함
+                    if updated.rows_affected() == 0 {
+                        skipped += 1;
+                    } else {
+                        done += 1;
+                    }
+                }
+                Err(e) => {
+                    warn!(post_id = id, error = %e, "Failed to re-embed post");
+                    failed += 1;
+                }
+            }
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO reembed_checkpoints (run_key, last_id, done, failed, skipped)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (run_key) DO UPDATE
+            SET last_id = EXCLUDED.last_id,
+                done = EXCLUDED.done,
+                failed = EXCLUDED.failed,
+                skipped = EXCLUDED.skipped,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(&run_key)
+        .bind(last_id)
+        .bind(done)
+        .bind(failed)
+        .bind(skipped)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        cursor = last_id;
+        info!(
+            done,
+            failed,
+            skipped,
+            total = report.selected,
+            "Re-embed progress"
+        );
+    }
+
+    // 바뀐 embedding 기준으로 edge, cluster, 캐시 갱신 (이전에 중단된 실행분 포함)
+    if done > 0 {
+        // 다시 만드는 동안에도 이전 edge가 보이도록 한 transaction에서
+        let mut tx = db.begin().await?;
+        graph::rebuild_edges(&mut tx, options.user_id).await?;
+        tx.commit().await?;
+        let users: Vec<i64> = sqlx::query_scalar(
+            "SELECT DISTINCT user_id FROM posts WHERE $1::BIGINT IS NULL OR user_id = $1",
+        )
+        .bind(options.user_id)
+        .fetch_all(db)
+        .await?;
+        for user_id in users {
+            graph::invalidate_graph(user_id).await;
+            if let Err(e) = cluster::recompute(db, user_id).await {
+                warn!(user_id, error = %e, "Failed to recompute clusters after re-embed");
+            }
+        }
+        related::clear().await;
+    }
+    sqlx::query("DELETE FROM reembed_checkpoints WHERE run_key = $1")
+        .bind(&run_key)
+        .execute(db)
+        .await?;
+
+    report.done = done;
+    report.failed = failed;
+    report.skipped = skipped;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target() -> Target {
+        Target {
+            model: "new-model".to_string(),
+            dimension: 3,
+        }
+    }
+
+    fn response(
+        embedding: Vec<f32>,
+        model: Option<&str>,
+    ) -> Result<EmbeddingResponse, EmbeddingApiError> {
+        Ok(EmbeddingResponse {
+            embedding,
+            model: model.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn accepts_embeddings_of_the_target_model() {
+        let embedding =
+            check_embedding(&target(), response(vec![0.1, 0.2, 0.3], Some("new-model")));
+        assert_eq!(embedding, Ok(vec![0.1, 0.2, 0.3]));
+        // model을 알려주지 않는 API는 차원만 확인
+        assert!(check_embedding(&target(), response(vec![0.0; 3], None)).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_dimension() {
+        let error = check_embedding(&target(), response(vec![0.0; 4], Some("new-model")));
+        assert_eq!(error, Err("expected 3 dimensions, got 4".to_string()));
+    }
+
+    #[test]
+    fn rejects_another_model() {
+        let error = check_embedding(&target(), response(vec![0.0; 3], Some("old-model")));
+        assert!(error.unwrap_err().contains("old-model"));
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let error = check_embedding(
+            &target(),
+            Err(EmbeddingApiError::Batch("embedder stopped".to_string())),
+        );
+        assert!(error.unwrap_err().contains("embedder stopped"));
+    }
+}
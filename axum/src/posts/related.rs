@@ -0,0 +1,344 @@
+// src/posts/related.rs
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Path,
+    http::{Method, Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::OnceCell;
+use redis::{AsyncCommands, aio::MultiplexedConnection};
+use sqlx::{Postgres, pool::Pool};
+use tracing::warn;
+
+use super::graph::get_related_post;
+use super::models::{Post, PostResponse};
+use super::utils::SharedConnection;
+use crate::config::SimilarityConfig;
+use crate::monitoring::ReachedHandler;
+
+// post id -> 최신 related_posts (JSON). write-back cache에 들어 있는 목록 대신 사용
+const RELATED_KEY: &str = "posts:related";
+// posts:related:by:{id} -> id를 목록에 가진 post들 (역색인)
+const RELATED_BY_PREFIX: &str = "posts:related:by";
+// 캐시에는 삭제됐지만 아직 DB에서 지워지지 않은 post
+pub const PENDING_DELETES_KEY: &str = "posts:pending_deletes";
+// 캐시된 GET 응답 최대 크기
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+static RELATED: OnceCell<RelatedCache> = OnceCell::new();
+
+/// Keeps `related_posts` of cached `GET /posts/:id` responses current.
+///
+/// The write-back cache stores whole `PostResponse`s, so the related list it
+/// serves is whatever was computed when the entry was filled. Lists are kept
+/// here instead, recomputed after a flush changed the embedding and dropped
+/// when a post they point to changes or is deleted.
+struct RelatedCache {
+    db: Pool<Postgres>,
+    redis: SharedConnection,
+    config: &'static SimilarityConfig,
+}
+
+pub fn init(db: Pool<Postgres>, client: redis::Client, config: &'static SimilarityConfig) {
+    let _ = RELATED.set(RelatedCache {
+        db,
+        redis: SharedConnection::new(client),
+        config,
+    });
+    tokio::spawn(async {
+        if let Some(cache) = RELATED.get()
+            && let Err(e) = cache.backfill_index().await
+        {
+            warn!(error = %e, "Failed to backfill related posts index");
+        }
+    });
+}
+
+impl RelatedCache {
+    async fn conn(&self) -> redis::RedisResult<MultiplexedConnection> {
+        self.redis.get().await
+    }
+
+    async fn reset(&self) {
+        self.redis.reset().await;
+    }
+
+    async fn get(&self, post_id: i64) -> Option<Vec<Post>> {
+        let mut conn = self.conn().await.ok()?;
+        let raw: Option<String> = match conn.hget(RELATED_KEY, post_id).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!(post_id, error = %e, "Failed to read related posts");
+                self.reset().await;
+                None
+            }
+        };
+        raw.and_then(|raw| serde_json::from_str(&raw).ok())
+    }
+
+    async fn store(&self, post_id: i64, related: &[Post]) {
+        let Ok(raw) = serde_json::to_string(related) else {
+            return;
+        };
+        let result = async {
+            let mut conn = self.conn().await?;
+            let old: Option<String> = conn.hget(RELATED_KEY, post_id).await?;
+            let (removed, added) = reindex(old.as_deref(), related);
+            let mut pipe = redis::pipe();
+            pipe.atomic().hset(RELATED_KEY, post_id, raw).ignore();
+            for target in removed {
+                pipe.srem(by_key(target), post_id).ignore();
+            }
+            for target in added {
+                pipe.sadd(by_key(target), post_id).ignore();
+            }
+            pipe.query_async::<()>(&mut conn).await
+        }
+        .await;
+        if let Err(e) = result {
+            warn!(post_id, error = %e, "Failed to store related posts");
+            self.reset().await;
+        }
+    }
+
+    async fn compute(&self, post_id: i64) -> Option<Vec<Post>> {
+        let post = sqlx::query_as::<_, Post>("SELECT * FROM posts WHERE id = $1")
+            .bind(post_id)
+            .fetch_optional(&self.db)
+            .await
+            .ok()??;
+        let pending = self.pending_deletes().await;
+        let related: Vec<Post> = get_related_post(&post, &self.db, self.config)
+            .await
+            .into_iter()
+            .filter(|related| !pending.contains(&related.id))
+            .collect();
+        self.store(post_id, &related).await;
+        Some(related)
+    }
+
+    async fn pending_deletes(&self) -> HashSet<i64> {
+        match self.conn().await {
+            Ok(mut conn) => conn.smembers(PENDING_DELETES_KEY).await.unwrap_or_default(),
+            Err(_) => HashSet::new(),
+        }
+    }
+
+    /// Indexes lists stored before the reverse index existed. Runs once at
+    /// startup and is idempotent.
+    async fn backfill_index(&self) -> redis::RedisResult<()> {
+        let mut conn = self.conn().await?;
+        let lists: HashMap<i64, String> = conn.hgetall(RELATED_KEY).await?;
+        let mut pipe = redis::pipe();
+        for (source, raw) in &lists {
+            for target in targets(Some(raw)) {
+                pipe.sadd(by_key(target), source).ignore();
+            }
+        }
+        pipe.query_async::<()>(&mut conn).await
+    }
+
+    /// Drops the list of `post_id` and every list that mentions it, found
+    /// through the reverse index.
+    async fn invalidate(&self, post_id: i64) -> redis::RedisResult<()> {
+        let mut conn = self.conn().await?;
+        let mut stale: Vec<i64> = conn.smembers(by_key(post_id)).await?;
+        stale.push(post_id);
+        let lists: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(RELATED_KEY)
+            .arg(&stale)
+            .query_async(&mut conn)
+            .await?;
+
+        // 지우는 목록이 가리키던 post의 역색인에서도 제거
+        let mut pipe = redis::pipe();
+        pipe.atomic().hdel(RELATED_KEY, &stale).ignore();
+        pipe.del(by_key(post_id)).ignore();
+        for (source, raw) in stale.iter().zip(lists) {
+            for target in targets(raw.as_deref()) {
+                pipe.srem(by_key(target), source).ignore();
+            }
+        }
+        pipe.query_async::<()>(&mut conn).await
+    }
+}
+
+fn by_key(post_id: i64) -> String {
+    format!("{RELATED_BY_PREFIX}:{post_id}")
+}
+
+/// Ids in a stored list; an unreadable list points nowhere.
+fn targets(raw: Option<&str>) -> HashSet<i64> {
+    raw.and_then(|raw| serde_json::from_str::<Vec<Post>>(raw).ok())
+        .map(|related| related.iter().map(|post| post.id).collect())
+        .unwrap_or_default()
+}
+
+/// Reverse-index changes when a stored list `old` is replaced by `new`:
+/// targets to drop the source from, and targets to add it to.
+fn reindex(old: Option<&str>, new: &[Post]) -> (Vec<i64>, Vec<i64>) {
+    let old = targets(old);
+    let new: HashSet<i64> = new.iter().map(|post| post.id).collect();
+    let mut removed: Vec<i64> = old.difference(&new).copied().collect();
+    let mut added: Vec<i64> = new.difference(&old).copied().collect();
+    removed.sort_unstable();
+    added.sort_unstable();
+    (removed, added)
+}
+
+/// Called after a flush stored new content (and embedding) for `post_id`.
+pub async fn refresh(post_id: i64) {
+    let Some(cache) = RELATED.get() else {
+        return;
+    };
+    if let Err(e) = cache.invalidate(post_id).await {
+        warn!(post_id, error = %e, "Failed to invalidate related posts");
+        cache.reset().await;
+    }
+    cache.compute(post_id).await;
+}
+
+/// Called once a delete was accepted; `flushed` is true when the row is gone
+/// from Postgres, false while the delete still sits in the write-back cache.
+pub async fn forget(post_id: i64, flushed: bool) {
+    let Some(cache) = RELATED.get() else {
+        return;
+    };
+    let result = async {
+        let mut conn = cache.conn().await?;
+        if flushed {
+            conn.srem::<_, _, ()>(PENDING_DELETES_KEY, post_id).await?;
+        } else {
+            conn.sadd::<_, _, ()>(PENDING_DELETES_KEY, post_id).await?;
+        }
+        cache.invalidate(post_id).await
+    }
+    .await;
+    if let Err(e) = result {
+        warn!(post_id, error = %e, "Failed to invalidate related posts after delete");
+        cache.reset().await;
+    }
+}
+
+/// Drops every stored list, e.g. after all embeddings were replaced.
+pub async fn clear() {
+    let Some(cache) = RELATED.get() else {
+        return;
+    };
+    let result = async {
+        let mut conn = cache.conn().await?;
+        let lists: HashMap<i64, String> = conn.hgetall(RELATED_KEY).await?;
+        let mut keys: HashSet<String> = lists
+            .values()
+            .flat_map(|raw| targets(Some(raw)))
+            .map(by_key)
+            .collect();
+        keys.insert(RELATED_KEY.to_string());
+        conn.del::<_, ()>(keys.into_iter().collect::<Vec<_>>())
+            .await
+    }
+    .await;
+    if let Err(e) = result {
+        warn!(error = %e, "Failed to clear related posts");
+        cache.reset().await;
+    }
+}
+
+/// Replaces `related_posts` in cache hits with the current list, and records
+/// the list computed by the handler on misses. Absorbed deletes are tracked so
+/// other posts stop pointing at them before the delete is flushed.
+pub async fn refresh_related(Path(id): Path<i64>, req: Request<Body>, next: Next) -> Response {
+    let Some(cache) = RELATED.get() else {
+        return next.run(req).await;
+    };
+    let method = req.method().clone();
+    let reached = req.extensions().get::<ReachedHandler>().cloned();
+
+    let res = next.run(req).await;
+    let served_by_cache = reached.is_some_and(|reached| !reached.get());
+    if !res.status().is_success() {
+        return res;
+    }
+    if method == Method::DELETE {
+        forget(id, !served_by_cache).await;
+        return res;
+    }
+    if method != Method::GET {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let Ok(mut post) = serde_json::from_slice::<PostResponse>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    if served_by_cache {
+        let related = match cache.get(id).await {
+            Some(related) => Some(related),
+            None => cache.compute(id).await,
+        };
+        let Some(related) = related else {
+            return Response::from_parts(parts, Body::from(bytes));
+        };
+        post.related_posts = related;
+    } else {
+        cache.store(id, &post.related_posts).await;
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    match serde_json::to_vec(&post) {
+        Ok(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn post(id: i64) -> Post {
+        Post {
+            id,
+            title: format!("post {id}"),
+            content: String::new(),
+            user_id: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            embedding: None,
+        }
+    }
+
+    #[test]
+    fn reindexes_changed_targets_only() {
+        let old = serde_json::to_string(&[post(1), post(2), post(3)]).unwrap();
+        let (removed, added) = reindex(Some(&old), &[post(2), post(3), post(4), post(5)]);
+        assert_eq!(removed, vec![1]);
+        assert_eq!(added, vec![4, 5]);
+    }
+
+    #[test]
+    fn first_list_adds_every_target() {
+        let (removed, added) = reindex(None, &[post(7), post(8)]);
+        assert!(removed.is_empty());
+        assert_eq!(added, vec![7, 8]);
+    }
+
+    #[test]
+    fn unreadable_list_has_no_targets() {
+        assert!(targets(Some("not json")).is_empty());
+        assert!(targets(None).is_empty());
+        assert_eq!(targets(Some("[]")), HashSet::new());
+    }
+}
@@ -0,0 +1,345 @@
+// src/posts/search.rs
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+
+use super::models::SearchQuery;
+
+/// Search query split into free text for the vector ranking and SQL filters.
+///
+/// Syntax of `q`, terms separated by spaces:
+///
+/// - `word`, `"exact phrase"`: embedded for ranking; phrases must also appear
+///   in the title or content (as plain text, without HTML tags or entities)
+/// - `-word`, `-"phrase"`: excluded from title and content; a quote left open
+///   runs to the end of `q`
+/// - `tag:rust`: content has the `#rust` hashtag (posts have no separate tags)
+/// - `in:Projects/`: title starts with the prefix (folders are `/` in titles)
+/// - `created:` / `updated:` with `2025-01-01`, `>2025-01-01`, `>=`, `<`, `<=`:
+///   UTC days
+/// - `has:embedding`, `-has:embedding`
+///
+/// `-` also negates `tag:` and `in:`. Unknown `key:value` terms are words.
+#[derive(Debug, Default)]
+pub struct ParsedQuery {
+    /// Words and phrases in query order; empty when only filters were given.
+    pub text: String,
+    /// Lower-cased words and phrases to highlight in snippets.
+    pub terms: Vec<String>,
+    pub filters: SearchFilters,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchFilters {
+    pub created: DateRange,
+    pub updated: DateRange,
+    pub prefixes: Vec<String>,
+    pub excluded_prefixes: Vec<String>,
+    pub tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub phrases: Vec<String>,
+    pub excluded: Vec<String>,
+    pub has_embedding: Option<bool>,
+}
+
+/// `[from, until)`; `None` is unbounded.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DateRange {
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+struct Token {
+    negated: bool,
+    quoted: bool,
+    text: String,
+}
+
+/// Parses `q` and the filter parameters next to it; errors are user-facing.
+pub fn parse(query: &SearchQuery) -> Result<ParsedQuery, String> {
+    let mut parsed = ParsedQuery::default();
+    let mut text = Vec::new();
+
+    for token in tokenize(&query.q) {
+        let filters = &mut parsed.filters;
+        if token.quoted {
+            if token.negated {
+                filters.excluded.push(token.text);
+            } else {
+                parsed.terms.push(token.text.to_lowercase());
+                filters.phrases.push(token.text.clone());
+                text.push(token.text);
+            }
+            continue;
+        }
+
+        let filter = token.text.split_once(':').filter(|(key, value)| {
+            !value.is_empty() && matches!(*key, "tag" | "in" | "created" | "updated" | "has")
+        });
+        match (filter, token.negated) {
+            (Some(("tag", tag)), negated) => {
+                let tag = validate_tag(tag)?;
+                if negated {
+                    filters.excluded_tags.push(tag);
+                } else {
+                    filters.tags.push(tag);
+                }
+            }
+            (Some(("in", prefix)), false) => filters.prefixes.push(prefix.to_string()),
+            (Some(("in", prefix)), true) => filters.excluded_prefixes.push(prefix.to_string()),
+            (Some(("created", expr)), false) => apply_date(&mut filters.created, "created", expr)?,
+            (Some(("updated", expr)), false) => apply_date(&mut filters.updated, "updated", expr)?,
+            (Some(("has", "embedding")), negated) => filters.has_embedding = Some(!negated),
+            (Some(("has", other)), _) => {
+                return Err(format!("unknown has:{other}, expected has:embedding"));
+            }
+            (Some((key, _)), true) => return Err(format!("-{key}: cannot be negated")),
+            (_, true) => filters.excluded.push(token.text),
+            (_, false) => {
+                parsed.terms.push(token.text.to_lowercase());
+                text.push(token.text);
+            }
+        }
+    }
+
+    let filters = &mut parsed.filters;
+    if let Some(expr) = &query.created {
+        apply_date(&mut filters.created, "created", expr)?;
+    }
+    if let Some(expr) = &query.updated {
+        apply_date(&mut filters.updated, "updated", expr)?;
+    }
+    if let Some(prefix) = query.prefix.as_ref().filter(|prefix| !prefix.is_empty()) {
+        filters.prefixes.push(prefix.clone());
+    }
+    for tag in query.tags.iter().flat_map(|tags| tags.split(',')) {
+        if !tag.trim().is_empty() {
+            filters.tags.push(validate_tag(tag.trim())?);
+        }
+    }
+    if query.missing_embedding {
+        filters.has_embedding = Some(false);
+    }
+
+    parsed.terms.sort();
+    parsed.terms.dedup();
+    parsed.text = text.join(" ");
+    Ok(parsed)
+}
+
+// `"..."` 안의 공백은 token을 나누지 않음
+fn tokenize(q: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = q.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+        let negated = chars.next_if_eq(&'-').is_some();
+        let quoted = chars.peek() == Some(&'"');
+        let mut text = String::new();
+        let mut in_quotes = false;
+        while let Some(c) = chars.next_if(|c| in_quotes || !c.is_whitespace()) {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            } else {
+                text.push(c);
+            }
+        }
+        if !text.is_empty() {
+            tokens.push(Token {
+                negated,
+                quoted,
+                text,
+            });
+        }
+    }
+    tokens
+}
+
+// SQL 정규식에 그대로 들어가므로 문자를 제한
+fn validate_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim_start_matches('#');
+    if tag.is_empty()
+        || !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+    {
+        return Err(format!(
+            "invalid tag \"{tag}\": use letters, digits, _, - and /"
+        ));
+    }
+    Ok(tag.to_lowercase())
+}
+
+fn apply_date(range: &mut DateRange, key: &str, expr: &str) -> Result<(), String> {
+    let (op, date) = [">=", "<=", ">", "<"]
+        .iter()
+        .find_map(|op| expr.strip_prefix(op).map(|date| (*op, date)))
+        .unwrap_or(("", expr));
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date \"{date}\" for {key}: expected YYYY-MM-DD"))?;
+    let start = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let next = start.checked_add_days(Days::new(1)).unwrap_or(start);
+
+    let (from, until) = match op {
+        ">" => (Some(next), None),
+        ">=" => (Some(start), None),
+        "<" => (None, Some(start)),
+        "<=" => (None, Some(next)),
+        _ => (Some(start), Some(next)),
+    };
+    // 같은 key가 여러 번 오면 교집합
+    if let Some(from) = from {
+        range.from = Some(range.from.map_or(from, |current| current.max(from)));
+    }
+    if let Some(until) = until {
+        range.until = Some(range.until.map_or(until, |current| current.min(until)));
+    }
+    Ok(())
+}
+
+/// `LIKE` pattern matching `text` literally between `prefix` and `suffix`
+/// wildcards.
+pub fn like_pattern(text: &str, prefix: &str, suffix: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("{prefix}{escaped}{suffix}")
+}
+
+/// Case-insensitive regex for `#tag` in content, not part of a longer word or
+/// an HTML entity such as `&#39;`.
+pub fn tag_pattern(tag: &str) -> String {
+    format!("(^|[^[:alnum:]_&])#{tag}([^[:alnum:]_/-]|$)")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn parse_q(q: &str) -> Result<ParsedQuery, String> {
+        parse(&SearchQuery {
+            q: q.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> Option<DateTime<Utc>> {
+        Some(Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn splits_words_and_quoted_phrases() {
+        let parsed = parse_q(r#"Rust  "async await" 메모"#).unwrap();
+        assert_eq!(parsed.text, "Rust async await 메모");
+        assert_eq!(parsed.terms, vec!["async await", "rust", "메모"]);
+        assert_eq!(parsed.filters.phrases, vec!["async await"]);
+    }
+
+    #[test]
+    fn negates_words_phrases_and_filters() {
+        let parsed =
+            parse_q(r#"rust -draft -"old notes" -tag:wip -in:Archive/ -has:embedding"#).unwrap();
+        assert_eq!(parsed.text, "rust");
+        assert_eq!(parsed.filters.excluded, vec!["draft", "old notes"]);
+        assert_eq!(parsed.filters.excluded_tags, vec!["wip"]);
+        assert_eq!(parsed.filters.excluded_prefixes, vec!["Archive/"]);
+        assert_eq!(parsed.filters.has_embedding, Some(false));
+        assert!(parse_q("-created:2025-01-01").is_err());
+    }
+
+    #[test]
+    fn unterminated_quote_runs_to_the_end() {
+        let parsed = parse_q(r#"rust "open phrase tag:x"#).unwrap();
+        assert_eq!(parsed.filters.phrases, vec!["open phrase tag:x"]);
+        assert!(parsed.filters.tags.is_empty());
+        // 따옴표만 있으면 무시
+        let parsed = parse_q(r#"rust """#).unwrap();
+        assert_eq!(parsed.text, "rust");
+        assert!(parsed.filters.phrases.is_empty());
+    }
+
+    #[test]
+    fn treats_unknown_keys_and_empty_values_as_words() {
+        let parsed = parse_q("http://example.com tag:").unwrap();
+        assert_eq!(parsed.text, "http://example.com tag:");
+        assert!(parsed.filters.tags.is_empty());
+        assert!(parse_q("has:tags").is_err());
+    }
+
+    #[test]
+    fn parses_date_bounds_as_utc_days() {
+        let parsed =
+            parse_q("created:>=2025-01-02 created:<2025-02-01 updated:2025-03-04").unwrap();
+        let filters = &parsed.filters;
+        assert_eq!(filters.created.from, day(2025, 1, 2));
+        assert_eq!(filters.created.until, day(2025, 2, 1));
+        assert_eq!(filters.updated.from, day(2025, 3, 4));
+        assert_eq!(filters.updated.until, day(2025, 3, 5));
+
+        let parsed = parse_q("created:>2025-01-02 created:<=2025-01-31").unwrap();
+        assert_eq!(parsed.filters.created.from, day(2025, 1, 3));
+        assert_eq!(parsed.filters.created.until, day(2025, 2, 1));
+    }
+
+    #[test]
+    fn intersects_repeated_date_bounds() {
+        let parsed = parse(&SearchQuery {
+            q: "created:>=2025-01-01".to_string(),
+            created: Some(">=2025-03-01".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(parsed.filters.created.from, day(2025, 3, 1));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert!(parse_q("created:2025-13-01").is_err());
+        assert!(parse_q("updated:>=yesterday").is_err());
+        assert!(
+            parse(&SearchQuery {
+                updated: Some("2025/01/01".to_string()),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn validates_tags() {
+        let parsed = parse(&SearchQuery {
+            q: "tag:#Rust".to_string(),
+            tags: Some("web/api, my_tag,".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(parsed.filters.tags, vec!["rust", "web/api", "my_tag"]);
+        assert!(parse_q("tag:a.b").is_err());
+        assert!(parse_q("tag:#").is_err());
+        assert!(parse_q("tag:x|y").is_err());
+    }
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(like_pattern("50%_off", "%", "%"), r"%50\%\_off%");
+        assert_eq!(like_pattern(r"C:\dir", "", "%"), r"C:\\dir%");
+    }
+
+    #[test]
+    fn filter_parameters_only() {
+        let parsed = parse(&SearchQuery {
+            prefix: Some("Projects/".to_string()),
+            missing_embedding: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(parsed.text.is_empty());
+        assert_eq!(parsed.filters.prefixes, vec!["Projects/"]);
+        assert_eq!(parsed.filters.has_embedding, Some(false));
+    }
+}
@@ -0,0 +1,326 @@
+// src/posts/snippet.rs
+
+use std::collections::HashSet;
+
+use super::models::{HighlightRange, MatchedField, Post};
+use super::recommend::strip_tags;
+
+// snippet 길이 (문자 수)
+const SNIPPET_CHARS: usize = 160;
+// 첫 match 앞에 함께 보여줄 문맥
+const LEADING_CHARS: usize = 40;
+const ELLIPSIS: char = '…';
+
+/// Why a search hit matched: the field and the piece of it shown to the user.
+pub struct Snippet {
+    pub matched_field: MatchedField,
+    pub text: String,
+    pub highlights: Vec<HighlightRange>,
+}
+
+#[derive(Clone, Copy)]
+struct Match {
+    start: usize,
+    end: usize,
+    term: usize,
+}
+
+/// Snippets for search hits, in the order of `posts`.
+///
+/// Hits containing a query term get [`best_snippet`]; hits found by the
+/// embedding alone show the start of their content. Nothing is embedded here,
+/// so a search costs a single query embedding.
+pub fn search_snippets(posts: &[Post], terms: &[String]) -> Vec<Snippet> {
+    posts
+        .iter()
+        .map(|post| {
+            best_snippet(&post.title, &post.content, terms)
+                .unwrap_or_else(|| leading_snippet(&post.content))
+        })
+        .collect()
+}
+
+/// Picks the field and the window of text that best explain a hit for `terms`,
+/// or `None` when no term occurs in the post.
+///
+/// Matching is a case-insensitive substring search, so Korean words still
+/// match with particles attached. The title wins when it matches at least as
+/// many distinct terms as the best window of content.
+pub fn best_snippet(title: &str, content: &str, terms: &[String]) -> Option<Snippet> {
+    let terms: Vec<Vec<char>> = terms
+        .iter()
+        .map(|term| lower_chars(term))
+        .filter(|term| !term.is_empty())
+        .collect();
+
+    let title_chars: Vec<char> = title.chars().collect();
+    let title_matches = find_matches(&title_chars, &terms);
+
+    let text = plain_text(content);
+    let content_matches = find_matches(&text, &terms);
+    if title_matches.is_empty() && content_matches.is_empty() {
+        return None;
+    }
+    let (start, end) = best_window(&text, &content_matches);
+    let in_window: Vec<Match> = content_matches
+        .into_iter()
+        .filter(|m| m.start >= start && m.end <= end)
+        .collect();
+
+    if !title_matches.is_empty() && distinct(&title_matches) >= distinct(&in_window) {
+        return Some(Snippet {
+            matched_field: MatchedField::Title,
+            text: title.to_string(),
+            highlights: ranges(&title_chars, 0, 0, &title_matches),
+        });
+    }
+    Some(window_snippet(&text, start, end, &in_window))
+}
+
+/// Start of the content, for hits nothing else explains.
+fn leading_snippet(content: &str) -> Snippet {
+    let text = plain_text(content);
+    window_snippet(&text, 0, window_end(&text, 0, 0), &[])
+}
+
+// 태그를 지우고 공백을 하나로 합친 본문
+fn plain_text(content: &str) -> Vec<char> {
+    strip_tags(content)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect()
+}
+
+fn window_snippet(text: &[char], start: usize, end: usize, matches: &[Match]) -> Snippet {
+    let mut snippet = String::new();
+    let mut prefix = 0;
+    if start > 0 {
+        snippet.push(ELLIPSIS);
+        prefix = ELLIPSIS.len_utf16();
+    }
+    snippet.extend(&text[start..end]);
+    if end < text.len() {
+        snippet.push(ELLIPSIS);
+    }
+    Snippet {
+        matched_field: MatchedField::Content,
+        text: snippet,
+        highlights: ranges(text, start, prefix, matches),
+    }
+}
+
+// 글자 수가 바뀌지 않도록 한 글자씩 소문자로
+fn lower_chars(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// Non-overlapping occurrences of any term, in text order.
+fn find_matches(text: &[char], terms: &[Vec<char>]) -> Vec<Match> {
+    let lower: Vec<char> = text
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let mut matches = Vec::new();
+    for (term_index, term) in terms.iter().enumerate() {
+        if term.len() > lower.len() {
+            continue;
+        }
+        for start in 0..=lower.len() - term.len() {
+            if lower[start..start + term.len()] == term[..] {
+                matches.push(Match {
+                    start,
+                    end: start + term.len(),
+                    term: term_index,
+                });
+            }
+        }
+    }
+    // 겹치면 먼저 시작하는(같으면 더 긴) match만 남김
+    matches.sort_by_key(|m| (m.start, std::cmp::Reverse(m.end)));
+    let mut kept: Vec<Match> = Vec::with_capacity(matches.len());
+    for m in matches {
+        if kept.last().is_none_or(|last| m.start >= last.end) {
+            kept.push(m);
+        }
+    }
+    kept
+}
+
+fn distinct(matches: &[Match]) -> usize {
+    matches.iter().map(|m| m.term).collect::<HashSet<_>>().len()
+}
+
+/// `[start, end)` of the snippet window with the most distinct terms (then
+/// most matches), cut at word boundaries.
+fn best_window(text: &[char], matches: &[Match]) -> (usize, usize) {
+    if matches.is_empty() {
+        return (0, window_end(text, 0, 0));
+    }
+    let mut best = (0, 0);
+    let mut best_score = (0, 0);
+    for anchor in matches {
+        let mut start = anchor.start.saturating_sub(LEADING_CHARS);
+        while start > 0 && start < anchor.start && !text[start - 1].is_whitespace() {
+            start += 1;
+        }
+        let end = window_end(text, start, anchor.end);
+        let inside: Vec<Match> = matches
+            .iter()
+            .filter(|m| m.start >= start && m.end <= end)
+            .copied()
+            .collect();
+        let score = (distinct(&inside), inside.len());
+        if score > best_score {
+            best = (start, end);
+            best_score = score;
+        }
+    }
+    best
+}
+
+fn window_end(text: &[char], start: usize, min_end: usize) -> usize {
+    let mut end = (start + SNIPPET_CHARS).min(text.len()).max(min_end);
+    if end == text.len() {
+        return end;
+    }
+    let limit = end;
+    while end > min_end.max(start) && !text[end].is_whitespace() {
+        end -= 1;
+    }
+    if end == min_end.max(start) && !text[end].is_whitespace() {
+        // 공백 없이 긴 단어는 그냥 자름
+        end = limit;
+    }
+    end
+}
+
+/// Match positions relative to the snippet, in UTF-16 code units (the way
+/// JavaScript indexes strings).
+fn ranges(
+    text: &[char],
+    window_start: usize,
+    prefix: usize,
+    matches: &[Match],
+) -> Vec<HighlightRange> {
+    let utf16 = |chars: &[char]| chars.iter().map(|c| c.len_utf16()).sum::<usize>();
+    matches
+        .iter()
+        .map(|m| {
+            let start = prefix + utf16(&text[window_start..m.start]);
+            HighlightRange {
+                start,
+                end: start + utf16(&text[m.start..m.end]),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|term| term.to_string()).collect()
+    }
+
+    fn highlighted(snippet: &Snippet) -> Vec<String> {
+        let units: Vec<u16> = snippet.text.encode_utf16().collect();
+        snippet
+            .highlights
+            .iter()
+            .map(|range| String::from_utf16(&units[range.start..range.end]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn no_snippet_without_a_match() {
+        assert!(best_snippet("제목", "<p>본문</p>", &terms(&["없음"])).is_none());
+        assert!(best_snippet("title", "body", &terms(&[""])).is_none());
+    }
+
+    #[test]
+    fn prefers_the_title_when_it_matches_as_many_terms() {
+        let snippet = best_snippet("Rust 메모", "rust is fun", &terms(&["rust"])).unwrap();
+        assert!(matches!(snippet.matched_field, MatchedField::Title));
+        assert_eq!(highlighted(&snippet), vec!["Rust"]);
+
+        let snippet =
+            best_snippet("Rust 메모", "rust async notes", &terms(&["rust", "async"])).unwrap();
+        assert!(matches!(snippet.matched_field, MatchedField::Content));
+        assert_eq!(highlighted(&snippet), vec!["rust", "async"]);
+    }
+
+    #[test]
+    fn matches_korean_words_with_particles() {
+        let snippet = best_snippet(
+            "일기",
+            "<p>오늘은 <b>데이터베이스를</b> 공부했다</p>",
+            &terms(&["데이터베이스"]),
+        )
+        .unwrap();
+        assert_eq!(snippet.text, "오늘은 데이터베이스를 공부했다");
+        assert_eq!(highlighted(&snippet), vec!["데이터베이스"]);
+    }
+
+    #[test]
+    fn counts_offsets_in_utf16_units() {
+        // 😀는 UTF-16으로 2 unit
+        let snippet = best_snippet("t", "😀😀 노트 rust", &terms(&["rust"])).unwrap();
+        assert_eq!(snippet.highlights[0].start, 8);
+        assert_eq!(snippet.highlights[0].end, 12);
+        assert_eq!(highlighted(&snippet), vec!["rust"]);
+    }
+
+    #[test]
+    fn keeps_the_earliest_longest_of_overlapping_matches() {
+        let snippet =
+            best_snippet("t", "database 정리", &terms(&["base", "database", "data"])).unwrap();
+        assert_eq!(highlighted(&snippet), vec!["database"]);
+    }
+
+    #[test]
+    fn cuts_a_window_around_a_late_match() {
+        let filler = "가나다 ".repeat(100);
+        let content = format!("{filler}핵심 단어 {filler}");
+        let snippet = best_snippet("t", &content, &terms(&["핵심"])).unwrap();
+        assert!(snippet.text.starts_with(ELLIPSIS));
+        assert!(snippet.text.ends_with(ELLIPSIS));
+        assert!(snippet.text.chars().count() <= SNIPPET_CHARS + 2);
+        // 앞쪽 문맥은 단어 경계에서 시작
+        assert!(snippet.text.starts_with("…가나다 "));
+        assert_eq!(highlighted(&snippet), vec!["핵심"]);
+    }
+
+    #[test]
+    fn keeps_matches_at_the_edges_of_the_text() {
+        let filler = "word ".repeat(60);
+        let content = format!("start {filler}finish");
+        let snippet = best_snippet("t", &content, &terms(&["start"])).unwrap();
+        assert!(!snippet.text.starts_with(ELLIPSIS));
+        assert_eq!(snippet.highlights[0].start, 0);
+
+        let snippet = best_snippet("t", &content, &terms(&["finish"])).unwrap();
+        assert!(snippet.text.ends_with("finish"));
+        assert_eq!(highlighted(&snippet), vec!["finish"]);
+    }
+
+    #[test]
+    fn picks_the_window_with_the_most_distinct_terms() {
+        let filler = "x ".repeat(200);
+        let content = format!("alpha {filler} alpha beta {filler}");
+        let snippet = best_snippet("t", &content, &terms(&["alpha", "beta"])).unwrap();
+        assert_eq!(highlighted(&snippet), vec!["alpha", "beta"]);
+    }
+
+    #[test]
+    fn cuts_long_words_without_spaces() {
+        let content = "가".repeat(400);
+        let snippet = leading_snippet(&content);
+        assert_eq!(snippet.text.chars().count(), SNIPPET_CHARS + 1);
+        assert!(snippet.highlights.is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+// src/posts/stats.rs
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+use jwt_authorizer::JwtClaims;
+use sqlx::{Postgres, pool::Pool};
+
+use super::graph::load_graph;
+use super::models::{
+    BridgeNode, GraphComponent, GraphData, GraphStats, GraphStatsNode, GraphStatsQuery,
+    NodeCentrality,
+};
+use crate::auth::UserClaims;
+use crate::config::Config;
+
+const DEFAULT_CENTRAL_LIMIT: usize = 20;
+const PAGERANK_DAMPING: f64 = 0.85;
+const PAGERANK_ITERATIONS: usize = 100;
+const PAGERANK_TOLERANCE: f64 = 1e-9;
+
+/// `GET /posts/graph/stats`: centrality, orphans, bridges and components of
+/// the same graph `GET /posts/graph` returns.
+pub async fn get_graph_stats(
+    State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+    Query(query): Query<GraphStatsQuery>,
+) -> Result<Json<GraphStats>, (StatusCode, String)> {
+    let graph = load_graph(&db, user.sub, &config.similarity).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_CENTRAL_LIMIT);
+    Ok(Json(analyze(&graph, limit)))
+}
+
+/// Undirected weighted adjacency list; A→B and B→A collapse into one edge.
+struct Adjacency {
+    neighbors: Vec<Vec<(usize, f64)>>,
+    edge_count: usize,
+}
+
+fn adjacency(graph: &GraphData) -> Adjacency {
+    let index: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), i))
+        .collect();
+
+    let mut weights: HashMap<(usize, usize), f64> = HashMap::new();
+    for link in &graph.links {
+        let (Some(&a), Some(&b)) = (
+            index.get(link.source.as_str()),
+            index.get(link.target.as_str()),
+        ) else {
+            continue;
+        };
+        if a == b {
+            continue;
+        }
+        let weight = weights.entry((a.min(b), a.max(b))).or_insert(0.0);
+        *weight = weight.max(link.value as f64);
+    }
+
+    let mut neighbors = vec![Vec::new(); graph.nodes.len()];
+    for (&(a, b), &weight) in &weights {
+        neighbors[a].push((b, weight));
+        neighbors[b].push((a, weight));
+    }
+    Adjacency {
+        neighbors,
+        edge_count: weights.len(),
+    }
+}
+
+fn analyze(graph: &GraphData, limit: usize) -> GraphStats {
+    let adjacency = adjacency(graph);
+    let ranks = pagerank(&adjacency.neighbors);
+    let node = |i: usize| GraphStatsNode {
+        id: graph.nodes[i].id.clone(),
+        name: graph.nodes[i].name.clone(),
+    };
+
+    let mut central: Vec<NodeCentrality> = graph
+        .nodes
+        .iter()
+        .zip(&adjacency.neighbors)
+        .zip(&ranks)
+        .map(|((node, neighbors), rank)| NodeCentrality {
+            id: node.id.clone(),
+            name: node.name.clone(),
+            degree: neighbors.len(),
+            pagerank: *rank,
+        })
+        .collect();
+    central.sort_by(|a, b| {
+        b.pagerank
+            .total_cmp(&a.pagerank)
+            .then_with(|| b.degree.cmp(&a.degree))
+    });
+    central.truncate(limit);
+
+    let orphans = (0..graph.nodes.len())
+        .filter(|&i| adjacency.neighbors[i].is_empty())
+        .map(node)
+        .collect();
+
+    // 자기 cluster 밖의 이웃이 있는 post
+    let bridges = graph
+        .nodes
+        .iter()
+        .zip(&adjacency.neighbors)
+        .filter_map(|(post, neighbors)| {
+            let connects: BTreeSet<i32> = neighbors
+                .iter()
+                .filter_map(|(j, _)| graph.nodes[*j].cluster_id)
+                .filter(|cluster| Some(*cluster) != post.cluster_id)
+                .collect();
+            (!connects.is_empty()).then(|| BridgeNode {
+                id: post.id.clone(),
+                name: post.name.clone(),
+                cluster_id: post.cluster_id,
+                connects: connects.into_iter().collect(),
+            })
+        })
+        .collect();
+
+    let components = components(&adjacency.neighbors)
+        .into_iter()
+        .map(|members| GraphComponent {
+            size: members.len(),
+            post_ids: members
+                .into_iter()
+                .map(|i| graph.nodes[i].id.clone())
+                .collect(),
+        })
+        .collect();
+
+    GraphStats {
+        node_count: graph.nodes.len(),
+        edge_count: adjacency.edge_count,
+        central,
+        orphans,
+        bridges,
+        components,
+    }
+}
+
+/// Weighted PageRank; rank of posts without neighbors is spread over all posts.
+fn pagerank(neighbors: &[Vec<(usize, f64)>]) -> Vec<f64> {
+    let n = neighbors.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let strength: Vec<f64> = neighbors
+        .iter()
+        .map(|edges| edges.iter().map(|(_, weight)| weight).sum())
+        .collect();
+
+    let mut ranks = vec![1.0 / n as f64; n];
+    for _ in 0..PAGERANK_ITERATIONS {
+        let dangling: f64 = (0..n)
+            .filter(|&i| strength[i] <= 0.0)
+            .map(|i| ranks[i])
+            .sum();
+        let base = (1.0 - PAGERANK_DAMPING + PAGERANK_DAMPING * dangling) / n as f64;
+        let mut next = vec![base; n];
+        for (i, edges) in neighbors.iter().enumerate() {
+            if strength[i] <= 0.0 {
+                continue;
+            }
+            for (j, weight) in edges {
+                next[*j] += PAGERANK_DAMPING * ranks[i] * weight / strength[i];
+            }
+        }
+
+        let delta: f64 = ranks.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        ranks = next;
+        if delta < PAGERANK_TOLERANCE {
+            break;
+        }
+    }
+    ranks
+}
+
+/// Connected components as node indexes, largest first.
+fn components(neighbors: &[Vec<(usize, f64)>]) -> Vec<Vec<usize>> {
+    let mut seen = vec![false; neighbors.len()];
+    let mut components = Vec::new();
+    for start in 0..neighbors.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut members = Vec::new();
+        while let Some(i) = stack.pop() {
+            members.push(i);
+            for (j, _) in &neighbors[i] {
+                if !seen[*j] {
+                    seen[*j] = true;
+                    stack.push(*j);
+                }
+            }
+        }
+        members.sort_unstable();
+        components.push(members);
+    }
+    components.sort_by_key(|members| std::cmp::Reverse(members.len()));
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::posts::models::{GraphLink, GraphNode};
+
+    fn node(id: &str, cluster_id: Option<i32>) -> GraphNode {
+        GraphNode {
+            id: id.to_string(),
+            name: format!("post {id}"),
+            created_at: None,
+            updated_at: None,
+            cluster_id,
+            cluster_label: None,
+        }
+    }
+
+    fn link(source: &str, target: &str, value: f32) -> GraphLink {
+        GraphLink {
+            source: source.to_string(),
+            target: target.to_string(),
+            value,
+        }
+    }
+
+    fn undirected(edges: &[(usize, usize, f64)], n: usize) -> Vec<Vec<(usize, f64)>> {
+        let mut neighbors = vec![Vec::new(); n];
+        for &(a, b, weight) in edges {
+            neighbors[a].push((b, weight));
+            neighbors[b].push((a, weight));
+        }
+        neighbors
+    }
+
+    #[test]
+    fn pagerank_sums_to_one() {
+        let neighbors = undirected(&[(0, 1, 1.0), (1, 2, 0.5)], 4);
+        let ranks = pagerank(&neighbors);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(pagerank(&[]).is_empty());
+    }
+
+    #[test]
+    fn pagerank_favors_the_hub() {
+        let neighbors = undirected(&[(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0)], 4);
+        let ranks = pagerank(&neighbors);
+        assert!(ranks[1..].iter().all(|rank| ranks[0] > *rank));
+        assert!((ranks[1] - ranks[2]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pagerank_follows_edge_weights() {
+        let neighbors = undirected(&[(0, 1, 0.9), (0, 2, 0.1)], 3);
+        let ranks = pagerank(&neighbors);
+        assert!(ranks[1] > ranks[2]);
+    }
+
+    #[test]
+    fn pagerank_spreads_isolated_posts_evenly() {
+        let ranks = pagerank(&vec![Vec::new(); 4]);
+        assert!(ranks.iter().all(|rank| (rank - 0.25).abs() < 1e-12));
+    }
+
+    #[test]
+    fn components_largest_first() {
+        let neighbors = undirected(&[(3, 4, 1.0), (4, 5, 1.0), (0, 1, 1.0)], 7);
+        assert_eq!(
+            components(&neighbors),
+            vec![vec![3, 4, 5], vec![0, 1], vec![2], vec![6]]
+        );
+    }
+
+    #[test]
+    fn adjacency_collapses_directions_and_drops_unknown_nodes() {
+        let graph = GraphData {
+            nodes: vec![node("1", None), node("2", None)],
+            links: vec![
+                link("1", "2", 0.5),
+                link("2", "1", 0.75),
+                link("1", "1", 1.0),
+                link("1", "9", 1.0),
+            ],
+        };
+        let adjacency = adjacency(&graph);
+        assert_eq!(adjacency.edge_count, 1);
+        assert_eq!(adjacency.neighbors[0], vec![(1, 0.75)]);
+        assert_eq!(adjacency.neighbors[1], vec![(0, 0.75)]);
+    }
+
+    #[test]
+    fn analyze_reports_orphans_and_bridges() {
+        let graph = GraphData {
+            nodes: vec![
+                node("1", Some(0)),
+                node("2", Some(0)),
+                node("3", Some(1)),
+                node("4", Some(1)),
+            ],
+            links: vec![link("1", "2", 0.9), link("2", "3", 0.6)],
+        };
+        let stats = analyze(&graph, 2);
+        assert_eq!(stats.node_count, 4);
+        assert_eq!(stats.edge_count, 2);
+        assert_eq!(stats.central.len(), 2);
+        assert_eq!(stats.central[0].id, "2");
+        let orphans: Vec<&str> = stats.orphans.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(orphans, vec!["4"]);
+        let bridges: Vec<(&str, &[i32])> = stats
+            .bridges
+            .iter()
+            .map(|bridge| (bridge.id.as_str(), bridge.connects.as_slice()))
+            .collect();
+        assert_eq!(bridges, vec![("2", &[1][..]), ("3", &[0][..])]);
+        assert_eq!(stats.components[0].post_ids, vec!["1", "2", "3"]);
+    }
+}
@@ -1,8 +1,15 @@
+use std::time::{Duration, Instant};
+
+use metrics::{counter, histogram};
+use once_cell::sync::OnceCell;
 use reqwest::{Client, StatusCode};
-use std::time::Duration;
+use serde::de::DeserializeOwned;
 use tokio::time::sleep;
 use tracing::{error, info, warn};
 
+use crate::config::EmbeddingConfig;
+use crate::telemetry::{REQUEST_ID_HEADER, current_request_id};
+
 // Define a custom error type for embedding API interactions
 #[derive(Debug, thiserror::Error)]
 pub enum EmbeddingApiError {
@@ -16,19 +23,48 @@ pub enum EmbeddingApiError {
     MaxRetriesExceeded,
     #[error("Embedding API is unhealthy")]
     Unhealthy,
+    #[error("Batch embedding failed: {0}")]
+    Batch(String),
+    #[error("Unexpected embedding: {0}")]
+    Mismatch(String),
+}
+
+static HTTP_CLIENT: OnceCell<Client> = OnceCell::new();
+
+/// Pooled client shared by every embedding API call (keeps connections alive
+/// instead of opening new ones per request).
+pub fn http_client(config: &EmbeddingConfig) -> Client {
+    HTTP_CLIENT
+        .get_or_init(|| {
+            Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()
+                .expect("failed to build embedding HTTP client")
+        })
+        .clone()
 }
 
-/// Calls the embedding API with retry logic.
-pub async fn call_embedding_api_with_retry<T>(
+/// POSTs `request_payload` to the embedding API with retries and decodes the
+/// answer as `R` (single or batch embedding response).
+pub async fn post_with_retry<T, R>(
+    client: &Client,
+    config: &EmbeddingConfig,
     embedding_api_url: &str,
     request_payload: T,
-) -> Result<super::models::EmbeddingResponse, EmbeddingApiError>
+) -> Result<R, EmbeddingApiError>
 where
     T: serde::Serialize,
+    R: DeserializeOwned,
 {
-    let client = Client::new();
-    let max_retries = 2;
-    let api_key = std::env::var("EMBED_API_KEY").unwrap_or_default();
+    let max_retries = config.max_retries;
+    let api_key = &config.api_key;
+    let request_id = current_request_id();
+    // metric label: 마지막 path segment (embed, query-embedding)
+    let endpoint = embedding_api_url
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
     let mut current_retry = 0;
 
     while current_retry < max_retries {
@@ -37,18 +73,26 @@ where
             current_retry + 1,
             max_retries
         );
-        match client
+        let mut request = client
             .post(embedding_api_url)
             .header("Authorization", format!("Bearer {}", api_key))
-            .json(&request_payload)
-            .send()
-            .await
-        {
+            .json(&request_payload);
+        if let Some(request_id) = &request_id {
+            request = request.header(REQUEST_ID_HEADER, request_id);
+        }
+        let started = Instant::now();
+        let result = request.send().await;
+        histogram!("embedding_request_duration_seconds", "endpoint" => endpoint.clone())
+            .record(started.elapsed().as_secs_f64());
+        if current_retry > 0 {
+            counter!("embedding_retries_total", "endpoint" => endpoint.clone()).increment(1);
+        }
+        match result {
             Ok(response) => {
                 let status = response.status();
                 if status.is_success() {
                     return Ok(response
-                        .json::<super::models::EmbeddingResponse>()
+                        .json::<R>()
                         .await
                         .map_err(EmbeddingApiError::HttpRequest)?);
                 } else {
@@ -72,59 +116,44 @@ where
         }
     }
     error!("Max retries exceeded for embedding API call.");
+    counter!("embedding_failures_total", "endpoint" => endpoint).increment(1);
     Err(EmbeddingApiError::MaxRetriesExceeded)
 }
 
-/// Checks the health of the embedding API.
-pub async fn check_embedding_api_health(health_check_url: &str) -> Result<(), EmbeddingApiError> {
-    let client = Client::new();
-    let max_retries = 6;
-    let api_key = std::env::var("EMBED_API_KEY").unwrap_or_default();
-    let mut current_retry = 0;
-
-    while current_retry < max_retries {
-        info!(
-            "Attempting health check (retry {}/{}) at: {}",
-            current_retry + 1,
-            max_retries,
-            health_check_url
-        );
-        match client
-            .get(health_check_url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
-            .await
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    info!("Embedding API is healthy.");
-                    return Ok(());
-                } else {
-                    warn!(
-                        "Embedding API health check failed with status: {}. Retrying...",
-                        response.status()
-                    );
-                    current_retry += 1;
-                    sleep(Duration::from_secs(2u64.pow(current_retry))).await; // Exponential backoff
-                }
-            }
-            Err(e) => {
-                error!(
-                    "Failed to reach embedding API for health check: {}. Retrying...",
-                    e
-                );
-                current_retry += 1;
-                sleep(Duration::from_secs(2u64.pow(current_retry))).await; // Exponential backoff
-            }
-        }
-    }
-    error!("Max retries exceeded for health check.");
-    Err(EmbeddingApiError::Unhealthy)
-}
-
 pub fn internal_error<E>(err: E) -> (StatusCode, String)
 where
     E: std::fmt::Display,
 {
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
 }
+
+/// Multiplexed Redis connection opened on first use and reopened after errors,
+/// for the cache helpers that run outside of app state.
+pub struct SharedConnection {
+    client: redis::Client,
+    conn: tokio::sync::Mutex<Option<redis::aio::MultiplexedConnection>>,
+}
+
+impl SharedConnection {
+    pub fn new(client: redis::Client) -> Self {
+        Self {
+            client,
+            conn: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub async fn get(&self) -> redis::RedisResult<redis::aio::MultiplexedConnection> {
+        let mut conn = self.conn.lock().await;
+        if let Some(conn) = conn.as_ref() {
+            return Ok(conn.clone());
+        }
+        let fresh = self.client.get_multiplexed_async_connection().await?;
+        *conn = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    /// Drops the connection so the next call reconnects.
+    pub async fn reset(&self) {
+        *self.conn.lock().await = None;
+    }
+}
@@ -0,0 +1,349 @@
+// src/rate_limit.rs
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::http::HeaderMap;
+use redis::AsyncCommands;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+// IP 하나당 허용되는 로그인 시도 (성공/실패 무관)
+const IP_WINDOW_SECS: u64 = 60;
+const MAX_ATTEMPTS_PER_IP: u64 = 20;
+// username 하나당 허용되는 로그인 시도
+const USERNAME_WINDOW_SECS: u64 = 60;
+const MAX_ATTEMPTS_PER_USERNAME: u64 = 10;
+// 연속 실패 시 점진적 잠금 (30s, 60s, 120s, ... 최대 1h)
+const FAILURE_WINDOW_SECS: u64 = 15 * 60;
+const FAILURES_BEFORE_LOCKOUT: u64 = 5;
+const BASE_LOCKOUT_SECS: u64 = 30;
+const MAX_LOCKOUT_SECS: u64 = 60 * 60;
+// in-memory fallback 정리 기준
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+// INCR과 만료 설정을 한 번에 (만료가 없는 key가 남아 영구히 잠기지 않도록)
+const INCR_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if redis.call('TTL', KEYS[1]) < 0 then
+    redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return count
+"#;
+
+/// Login throttling state shared between requests.
+///
+/// Counters live in Redis so every replica sees the same attempts; when Redis
+/// is unreachable the limiter keeps working from a per-process map instead.
+#[derive(Clone)]
+pub struct LoginLimiter {
+    redis: Option<redis::aio::MultiplexedConnection>,
+    memory: Arc<Mutex<HashMap<String, (u64, Instant)>>>,
+}
+
+impl LoginLimiter {
+    pub async fn new(redis_url: &str) -> Self {
+        let redis = match redis::Client::open(redis_url) {
+            Ok(client) => match client.get_multiplexed_async_connection().await {
+                Ok(conn) => Some(conn),
+                Err(e) => {
+                    warn!(error = %e, "Login limiter: Redis unavailable, using in-memory counters");
+                    None
+                }
+            },
+            Err(e) => {
+                warn!(error = %e, "Login limiter: invalid REDIS_URL, using in-memory counters");
+                None
+            }
+        };
+
+        Self {
+            redis,
+            memory: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Counts a login attempt and returns `Err(retry_after_secs)` if it must be rejected.
+    pub async fn check(&self, ip: &str, username: &str) -> Result<(), u64> {
+        for key in [lock_key("user", username), lock_key("ip", ip)] {
+            if let Some(remaining) = self.remaining(&key).await {
+                return Err(remaining);
+            }
+        }
+
+        let ip_key = format!("login:attempts:ip:{ip}");
+        if self.incr(&ip_key, IP_WINDOW_SECS).await > MAX_ATTEMPTS_PER_IP {
+            return Err(self.remaining(&ip_key).await.unwrap_or(IP_WINDOW_SECS));
+        }
+
+        let user_key = format!("login:attempts:user:{username}");
+        if self.incr(&user_key, USERNAME_WINDOW_SECS).await > MAX_ATTEMPTS_PER_USERNAME {
+            return Err(self
+                .remaining(&user_key)
+                .await
+                .unwrap_or(USERNAME_WINDOW_SECS));
+        }
+
+        Ok(())
+    }
+
+    /// Records a failed attempt and locks the username / IP once failures pile up.
+    pub async fn record_failure(&self, ip: &str, username: &str) {
+        for (scope, id) in [("user", username), ("ip", ip)] {
+            let failures = self
+                .incr(&format!("login:failures:{scope}:{id}"), FAILURE_WINDOW_SECS)
+                .await;
+            if failures >= FAILURES_BEFORE_LOCKOUT {
+                let lockout = lockout_secs(failures);
+                warn!(scope, id, failures, lockout_secs = lockout, "login locked");
+                self.set_for(&lock_key(scope, id), lockout).await;
+            }
+        }
+    }
+
+    /// Resets the failure streak of a username after a successful login.
+    pub async fn record_success(&self, username: &str) {
+        self.clear(&format!("login:failures:user:{username}")).await;
+    }
+
+    async fn incr(&self, key: &str, window_secs: u64) -> u64 {
+        if let Some(mut conn) = self.redis.clone() {
+            let result: redis::RedisResult<u64> = redis::Script::new(INCR_SCRIPT)
+                .key(key)
+                .arg(window_secs)
+                .invoke_async(&mut conn)
+                .await;
+            match result {
+                Ok(count) => return count,
+                Err(e) => warn!(error = %e, "Login limiter: Redis INCR failed, falling back"),
+            }
+        }
+
+        let mut memory = self.memory.lock().await;
+        let now = Instant::now();
+        if memory.len() > MEMORY_PRUNE_THRESHOLD {
+            memory.retain(|_, (_, expires_at)| *expires_at > now);
+        }
+        let entry = memory
+            .entry(key.to_string())
+            .or_insert((0, now + Duration::from_secs(window_secs)));
+        if entry.1 <= now {
+            *entry = (0, now + Duration::from_secs(window_secs));
+        }
+        entry.0 += 1;
+        entry.0
+    }
+
+    async fn set_for(&self, key: &str, secs: u64) {
+        if let Some(mut conn) = self.redis.clone() {
+            match conn.set_ex::<_, _, ()>(key, 1, secs).await {
+                Ok(()) => return,
+                Err(e) => warn!(error = %e, "Login limiter: Redis SET failed, falling back"),
+            }
+        }
+
+        let mut memory = self.memory.lock().await;
+        memory.insert(
+            key.to_string(),
+            (1, Instant::now() + Duration::from_secs(secs)),
+        );
+    }
+
+    /// Remaining lifetime of a key in seconds, `None` if it does not exist.
+    async fn remaining(&self, key: &str) -> Option<u64> {
+        if let Some(mut conn) = self.redis.clone() {
+            match conn.ttl::<_, i64>(key).await {
+                // -2: key 없음, -1: 만료 없음 (사용하지 않음)
+                Ok(ttl) if ttl > 0 => return Some(ttl as u64),
+                Ok(_) => return None,
+                Err(e) => warn!(error = %e, "Login limiter: Redis TTL failed, falling back"),
+            }
+        }
+
+        let memory = self.memory.lock().await;
+        let now = Instant::now();
+        memory
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(_, expires_at)| (*expires_at - now).as_secs().max(1))
+    }
+
+    async fn clear(&self, key: &str) {
+        if let Some(mut conn) = self.redis.clone()
+            && let Err(e) = conn.del::<_, ()>(key).await
+        {
+            warn!(error = %e, "Login limiter: Redis DEL failed");
+        }
+        self.memory.lock().await.remove(key);
+    }
+}
+
+fn lock_key(scope: &str, id: &str) -> String {
+    format!("login:lock:{scope}:{id}")
+}
+
+fn lockout_secs(failures: u64) -> u64 {
+    let doublings = (failures - FAILURES_BEFORE_LOCKOUT).min(16) as u32;
+    BASE_LOCKOUT_SECS
+        .saturating_mul(2u64.pow(doublings))
+        .min(MAX_LOCKOUT_SECS)
+}
+
+/// Client address used for rate limiting.
+///
+/// `X-Forwarded-For` is honored only when the peer is one of
+/// `trusted_proxies`. Hops left of the last trusted proxy were written by the
+/// client and can be forged, so the right-most hop that is not a trusted proxy
+/// is taken.
+pub fn client_ip(headers: &HeaderMap, addr: &SocketAddr, trusted_proxies: &[IpAddr]) -> String {
+    let peer = addr.ip().to_canonical();
+    if !trusted_proxies.contains(&peer) {
+        return peer.to_string();
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        // 형식이 잘못된 hop부터는 믿을 수 없음
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+        client = ip.to_canonical();
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+    }
+    client.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> LoginLimiter {
+        LoginLimiter {
+            redis: None,
+            memory: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn forwarded(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().unwrap());
+        headers
+    }
+
+    fn addr(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 443)
+    }
+
+    #[tokio::test]
+    async fn limits_attempts_per_username() {
+        let limiter = limiter();
+        for _ in 0..MAX_ATTEMPTS_PER_USERNAME {
+            assert!(limiter.check("10.0.0.1", "alice").await.is_ok());
+        }
+        let retry_after = limiter.check("10.0.0.2", "alice").await.unwrap_err();
+        assert!((1..=USERNAME_WINDOW_SECS).contains(&retry_after));
+        assert!(limiter.check("10.0.0.2", "bob").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn limits_attempts_per_ip() {
+        let limiter = limiter();
+        for i in 0..MAX_ATTEMPTS_PER_IP {
+            assert!(limiter.check("10.0.0.1", &format!("user{i}")).await.is_ok());
+        }
+        assert!(limiter.check("10.0.0.1", "someone").await.is_err());
+        assert!(limiter.check("10.0.0.2", "someone").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn locks_out_after_repeated_failures() {
+        let limiter = limiter();
+        for _ in 0..FAILURES_BEFORE_LOCKOUT - 1 {
+            limiter.record_failure("10.0.0.1", "alice").await;
+        }
+        assert!(limiter.check("10.0.0.9", "alice").await.is_ok());
+
+        limiter.record_failure("10.0.0.1", "alice").await;
+        let retry_after = limiter.check("10.0.0.9", "alice").await.unwrap_err();
+        assert!((1..=BASE_LOCKOUT_SECS).contains(&retry_after));
+        // IP도 함께 잠김
+        assert!(limiter.check("10.0.0.1", "bob").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn success_resets_failure_streak() {
+        let limiter = limiter();
+        for _ in 0..FAILURES_BEFORE_LOCKOUT - 1 {
+            limiter.record_failure("10.0.0.1", "alice").await;
+        }
+        limiter.record_success("alice").await;
+        for _ in 0..FAILURES_BEFORE_LOCKOUT - 1 {
+            limiter.record_failure("10.0.0.2", "alice").await;
+        }
+        assert!(limiter.check("10.0.0.3", "alice").await.is_ok());
+    }
+
+    #[test]
+    fn lockout_doubles_up_to_the_cap() {
+        assert_eq!(lockout_secs(FAILURES_BEFORE_LOCKOUT), BASE_LOCKOUT_SECS);
+        assert_eq!(
+            lockout_secs(FAILURES_BEFORE_LOCKOUT + 1),
+            2 * BASE_LOCKOUT_SECS
+        );
+        assert_eq!(lockout_secs(FAILURES_BEFORE_LOCKOUT + 30), MAX_LOCKOUT_SECS);
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        let headers = forwarded("1.2.3.4");
+        assert_eq!(
+            client_ip(&headers, &addr("203.0.113.7"), &[]),
+            "203.0.113.7"
+        );
+    }
+
+    #[test]
+    fn takes_right_most_untrusted_hop() {
+        let trusted = ["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+        // 클라이언트가 보낸 1.1.1.1은 무시
+        let headers = forwarded("1.1.1.1, 198.51.100.4, 10.0.0.2");
+        assert_eq!(
+            client_ip(&headers, &addr("10.0.0.1"), &trusted),
+            "198.51.100.4"
+        );
+    }
+
+    #[test]
+    fn stops_at_malformed_hops() {
+        let trusted = ["10.0.0.1".parse().unwrap()];
+        let headers = forwarded("198.51.100.4, not-an-ip");
+        assert_eq!(client_ip(&headers, &addr("10.0.0.1"), &trusted), "10.0.0.1");
+        assert_eq!(
+            client_ip(&HeaderMap::new(), &addr("10.0.0.1"), &trusted),
+            "10.0.0.1"
+        );
+    }
+
+    #[test]
+    fn normalizes_ipv4_mapped_peers() {
+        let trusted = ["10.0.0.1".parse().unwrap()];
+        let headers = forwarded("198.51.100.4");
+        assert_eq!(
+            client_ip(&headers, &addr("::ffff:10.0.0.1"), &trusted),
+            "198.51.100.4"
+        );
+    }
+}
@@ -10,14 +10,15 @@ use axum::{
 use jwt_authorizer::JwtClaims;
 
 use crate::models::{Comment, CreateComment, CreateUser, User};
+use crate::state::AppState;
 use serde::{Deserialize, Serialize};
 use sqlx::{Postgres, pool::Pool};
 
-pub fn public_routes() -> Router<Pool<Postgres>> {
+pub fn public_routes() -> Router<AppState> {
     Router::new().route("/accounts", get(list_accounts).post(create_account))
 }
 
-pub fn routes() -> Router<Pool<Postgres>> {
+pub fn routes() -> Router<AppState> {
     Router::new().route("/comments", get(list_comments).post(create_comment))
 }
 async fn create_comment(
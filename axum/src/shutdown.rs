@@ -0,0 +1,77 @@
+// src/shutdown.rs
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+
+use crate::config::ShutdownConfig;
+
+/// Set once a shutdown signal arrives; new writes are refused from then on.
+#[derive(Clone, Default)]
+pub struct Drain(Arc<AtomicBool>);
+
+impl Drain {
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn start(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Rejects non-GET requests with 503 while draining, so nothing new lands in
+/// the write-back cache after the final flush has started.
+pub async fn reject_writes(State(drain): State<Drain>, req: Request<Body>, next: Next) -> Response {
+    let read_only = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if drain.is_draining() && !read_only {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "5")],
+            "server is shutting down".to_string(),
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
+/// Resolves on SIGTERM or Ctrl+C after the drain period.
+///
+/// Draining starts immediately, so `/readyz` fails and the pod is taken out of
+/// the Service before the listener stops.
+pub async fn signal(drain: Drain, config: &ShutdownConfig) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for ctrl_c");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Ctrl+C received. Draining..."),
+        _ = terminate => info!("SIGTERM received. Draining..."),
+    }
+
+    drain.start();
+    tokio::time::sleep(config.drain()).await;
+    info!("Drain period over, stopping listener");
+}
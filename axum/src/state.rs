@@ -0,0 +1,25 @@
+// src/state.rs
+
+use axum::extract::FromRef;
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::{Postgres, pool::Pool};
+
+use crate::config::Config;
+use crate::oidc::OidcClient;
+use crate::posts::Embedder;
+use crate::rate_limit::LoginLimiter;
+use crate::shutdown::Drain;
+
+/// Shared application state. Handlers keep extracting only what they need
+/// (e.g. `State<Pool<Postgres>>`) through `FromRef`.
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub db: Pool<Postgres>,
+    pub redis: redis::Client,
+    pub config: &'static Config,
+    pub login_limiter: LoginLimiter,
+    pub oidc: Option<OidcClient>,
+    pub metrics: PrometheusHandle,
+    pub drain: Drain,
+    pub embedder: Embedder,
+}
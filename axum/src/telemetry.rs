@@ -0,0 +1,114 @@
+// src/telemetry.rs
+
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{HeaderName, Request},
+    middleware::Next,
+    response::Response,
+};
+use jwt_authorizer::JwtClaims;
+use tracing::{Span, info_span};
+use tracing_subscriber::{EnvFilter, fmt};
+
+use crate::auth::UserClaims;
+use crate::config::{LogConfig, LogFormat};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Installs the global tracing subscriber. `RUST_LOG` takes precedence over `log.level`.
+pub fn init(config: &LogConfig) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.level))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let builder = fmt().with_env_filter(filter).with_target(true);
+    match config.format {
+        LogFormat::Json => builder.json().flatten_event(true).init(),
+        LogFormat::Pretty => builder.init(),
+    }
+}
+
+/// Span for one HTTP request; `user_id` is filled in once the token is verified.
+pub fn make_span(req: &Request<Body>) -> Span {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("-");
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or_else(|| req.uri().path());
+
+    info_span!(
+        "request",
+        request_id,
+        method = %req.method(),
+        route,
+        user_id = tracing::field::Empty,
+    )
+}
+
+/// Makes the request id available to outgoing calls made while handling the request.
+pub async fn scope_request_id(req: Request<Body>, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    REQUEST_ID.scope(request_id, next.run(req)).await
+}
+
+/// Records the authenticated user on the request span.
+pub async fn record_user(
+    JwtClaims(user): JwtClaims<UserClaims>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    Span::current().record("user_id", user.sub);
+    next.run(req).await
+}
+
+/// Request id of the request currently being handled, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID
+        .try_with(|id| id.clone())
+        .ok()
+        .filter(|id| !id.is_empty())
+}
+
+/// Runs `fut` with `request_id` as the current request id, for work that was
+/// handed off from a request to another task.
+pub async fn with_request_id<F: Future>(request_id: Option<String>, fut: F) -> F::Output {
+    REQUEST_ID.scope(request_id.unwrap_or_default(), fut).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn current_request_id_is_scoped_to_the_request() {
+        assert_eq!(current_request_id(), None);
+        let id = REQUEST_ID
+            .scope("req-1".to_string(), async { current_request_id() })
+            .await;
+        assert_eq!(id.as_deref(), Some("req-1"));
+        assert_eq!(current_request_id(), None);
+    }
+
+    #[tokio::test]
+    async fn empty_request_id_is_ignored() {
+        let id = REQUEST_ID
+            .scope(String::new(), async { current_request_id() })
+            .await;
+        assert_eq!(id, None);
+    }
+}
@@ -0,0 +1,308 @@
+// src/tokens.rs
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Json, Path, State},
+    http::{HeaderValue, Method, Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use chrono::{Duration, Utc};
+use jwt_authorizer::JwtClaims;
+use sha2::{Digest, Sha256};
+use sqlx::{Postgres, pool::Pool};
+
+use crate::auth::{UserClaims, issue_access_token};
+use crate::config::Config;
+use crate::models::{ApiToken, CreateApiToken, CreatedApiToken, TokenScope};
+use crate::posts::internal_error;
+use crate::state::AppState;
+
+// 개인 토큰은 JWT와 구분되도록 고정 prefix를 붙임
+const TOKEN_PREFIX: &str = "nn_pat_";
+// expires_in_days 허용 범위
+const MAX_EXPIRY_DAYS: i64 = 365;
+
+/// Routes a personal access token may call, as `(method, path)` with `:id`
+/// for a numeric segment. Anything else (`/me/*`, `/auth/*`, ...) needs a
+/// signed-in session.
+const TOKEN_ROUTES: &[(Method, &str)] = &[
+    (Method::GET, "/posts"),
+    (Method::POST, "/posts"),
+    (Method::GET, "/posts/search"),
+    (Method::GET, "/posts/graph"),
+    (Method::GET, "/posts/graph/stats"),
+    (Method::GET, "/posts/:id"),
+    (Method::PUT, "/posts/:id"),
+    (Method::DELETE, "/posts/:id"),
+    (Method::GET, "/posts/:id/related"),
+    (Method::GET, "/posts/:id/graph"),
+    (Method::GET, "/clusters"),
+    (Method::GET, "/comments"),
+    (Method::POST, "/comments"),
+];
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me/tokens", get(list_tokens).post(create_token))
+        .route("/me/tokens/:id", delete(delete_token))
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+async fn create_token(
+    State(db): State<Pool<Postgres>>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+    Json(payload): Json<CreateApiToken>,
+) -> Result<Json<CreatedApiToken>, (StatusCode, String)> {
+    if payload.scopes.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "at least one scope is required".to_string(),
+        ));
+    }
+
+    let token = format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let scopes: Vec<String> = payload
+        .scopes
+        .iter()
+        .map(|scope| scope.as_str().to_string())
+        .collect();
+    let invalid_expiry = || {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("expires_in_days must be between 1 and {MAX_EXPIRY_DAYS}"),
+        )
+    };
+    let expires_at = match payload.expires_in_days {
+        Some(days @ 1..=MAX_EXPIRY_DAYS) => Some(
+            Utc::now()
+                .checked_add_signed(Duration::days(days))
+                .ok_or_else(invalid_expiry)?,
+        ),
+        Some(_) => return Err(invalid_expiry()),
+        None => None,
+    };
+
+    let api_token = sqlx::query_as::<_, ApiToken>(
+        r#"
+        INSERT INTO api_tokens (user_id, name, token_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, user_id, name, scopes, created_at, last_used_at, expires_at
+        "#,
+    )
+    .bind(user.sub)
+    .bind(&payload.name)
+    .bind(hash_token(&token))
+    .bind(&scopes)
+    .bind(expires_at)
+    .fetch_one(&db)
+    .await
+    .map_err(internal_error)?;
+
+    // 평문 토큰은 생성 시 한 번만 반환
+    Ok(Json(CreatedApiToken { token, api_token }))
+}
+
+async fn list_tokens(
+    State(db): State<Pool<Postgres>>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+) -> Result<Json<Vec<ApiToken>>, (StatusCode, String)> {
+    let tokens = sqlx::query_as::<_, ApiToken>(
+        r#"
+        SELECT id, user_id, name, scopes, created_at, last_used_at, expires_at
+        FROM api_tokens
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user.sub)
+    .fetch_all(&db)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(tokens))
+}
+
+async fn delete_token(
+    State(db): State<Pool<Postgres>>,
+    Path(id): Path<i64>,
+    JwtClaims(user): JwtClaims<UserClaims>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user.sub)
+        .execute(&db)
+        .await
+        .map_err(internal_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Token not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Whether a token with `scopes` may call `method path`.
+fn is_allowed(scopes: &[TokenScope], method: &Method, path: &str) -> bool {
+    let listed = TOKEN_ROUTES
+        .iter()
+        .any(|(route_method, pattern)| route_method == method && route_matches(pattern, path));
+    if !listed {
+        return false;
+    }
+    scopes.iter().any(|scope| match scope {
+        TokenScope::Write => true,
+        TokenScope::Read => method == Method::GET,
+        TokenScope::Search => method == Method::GET && path == "/posts/search",
+    })
+}
+
+fn route_matches(pattern: &str, path: &str) -> bool {
+    let mut segments = path.split('/');
+    let matched = pattern.split('/').all(|expected| {
+        segments.next().is_some_and(|segment| match expected {
+            ":id" => segment.parse::<i64>().is_ok(),
+            _ => segment == expected,
+        })
+    });
+    matched && segments.next().is_none()
+}
+
+/// Accepts personal access tokens on protected routes.
+///
+/// A valid token is swapped for a short-lived local access token before it
+/// reaches the `jwt_authorizer` layer, after checking its scopes against the
+/// request.
+pub async fn personal_tokens(
+    State(db): State<Pool<Postgres>>,
+    State(config): State<&'static Config>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, Response> {
+    let Some(token) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| token.starts_with(TOKEN_PREFIX))
+        .map(str::to_string)
+    else {
+        return Ok(next.run(req).await);
+    };
+
+    // 조회와 동시에 last_used_at 갱신
+    let row: Option<(i64, String, Vec<String>)> = sqlx::query_as(
+        r#"
+        UPDATE api_tokens
+        SET last_used_at = CURRENT_TIMESTAMP
+        FROM users
+        WHERE api_tokens.token_hash = $1
+        AND users.id = api_tokens.user_id
+        AND (api_tokens.expires_at IS NULL OR api_tokens.expires_at > CURRENT_TIMESTAMP)
+        RETURNING api_tokens.user_id, users.username, api_tokens.scopes
+        "#,
+    )
+    .bind(hash_token(&token))
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| internal_error(e).into_response())?;
+
+    let Some((user_id, username, scopes)) = row else {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid API token".to_string()).into_response());
+    };
+
+    let scopes: Vec<TokenScope> = scopes
+        .iter()
+        .filter_map(|scope| TokenScope::parse(scope))
+        .collect();
+    if !is_allowed(&scopes, req.method(), req.uri().path()) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "API token scope does not allow this request".to_string(),
+        )
+            .into_response());
+    }
+
+    let local_token =
+        issue_access_token(&config.jwt, user_id, &username).map_err(IntoResponse::into_response)?;
+    let value = HeaderValue::from_str(&format!("Bearer {local_token}"))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    req.headers_mut().insert(header::AUTHORIZATION, value);
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_scope_allows_only_listed_reads() {
+        let read = [TokenScope::Read];
+        assert!(is_allowed(&read, &Method::GET, "/posts"));
+        assert!(is_allowed(&read, &Method::GET, "/posts/42"));
+        assert!(is_allowed(&read, &Method::GET, "/posts/42/related"));
+        assert!(is_allowed(&read, &Method::GET, "/clusters"));
+        assert!(!is_allowed(&read, &Method::POST, "/posts"));
+        assert!(!is_allowed(&read, &Method::DELETE, "/posts/42"));
+    }
+
+    #[test]
+    fn search_scope_allows_only_search() {
+        let search = [TokenScope::Search];
+        assert!(is_allowed(&search, &Method::GET, "/posts/search"));
+        assert!(!is_allowed(&search, &Method::GET, "/posts"));
+        assert!(!is_allowed(&search, &Method::GET, "/posts/42"));
+    }
+
+    #[test]
+    fn write_scope_covers_post_routes() {
+        let write = [TokenScope::Write];
+        assert!(is_allowed(&write, &Method::POST, "/posts"));
+        assert!(is_allowed(&write, &Method::PUT, "/posts/42"));
+        assert!(is_allowed(&write, &Method::DELETE, "/posts/42"));
+        assert!(is_allowed(&write, &Method::GET, "/posts/graph/stats"));
+        assert!(is_allowed(&write, &Method::POST, "/comments"));
+    }
+
+    #[test]
+    fn account_management_is_never_allowed() {
+        let all = [TokenScope::Read, TokenScope::Write, TokenScope::Search];
+        for (method, path) in [
+            (Method::GET, "/me/tokens"),
+            (Method::POST, "/me/tokens"),
+            (Method::DELETE, "/me/tokens/3"),
+            (Method::GET, "/me"),
+            (Method::POST, "/auth/oidc/link"),
+            (Method::POST, "/admin/cache/flush"),
+        ] {
+            assert!(!is_allowed(&all, &method, path), "{method} {path}");
+        }
+    }
+
+    #[test]
+    fn unlisted_paths_and_methods_are_denied() {
+        let write = [TokenScope::Write];
+        assert!(!is_allowed(&write, &Method::PATCH, "/posts/42"));
+        assert!(!is_allowed(&write, &Method::GET, "/posts/abc"));
+        assert!(!is_allowed(&write, &Method::GET, "/posts/42/extra"));
+        assert!(!is_allowed(&write, &Method::GET, "/posts/"));
+        assert!(!is_allowed(&[], &Method::GET, "/posts"));
+    }
+
+    #[test]
+    fn matches_numeric_segments() {
+        assert!(route_matches("/posts/:id", "/posts/-1"));
+        assert!(route_matches("/posts/:id/graph", "/posts/7/graph"));
+        assert!(!route_matches("/posts/:id", "/posts"));
+        assert!(!route_matches("/posts", "/posts/7"));
+    }
+}
@@ -1,8 +1,9 @@
 import os
 from fastapi import FastAPI, Depends, HTTPException, status
 from fastapi.security import HTTPBearer, HTTPAuthorizationCredentials
+from fastapi.concurrency import run_in_threadpool
 from pydantic import BaseModel
-from typing import List
+from typing import Dict, List, Optional, Union
 from sentence_transformers import SentenceTransformer
 from threading import Lock
 from dotenv import load_dotenv
@@ -25,52 +26,83 @@ async def get_api_key(credentials: HTTPAuthorizationCredentials = Depends(securi
 # FastAPI 앱 생성
 app = FastAPI(title="Local LLM Embedding API")
 
-# Lazy 로딩용 전역 변수
-model = None
+DEFAULT_MODEL = os.getenv("EMBED_MODEL", "distiluse-base-multilingual-cased-v2")
+# 요청으로 로딩할 수 있는 model (쉼표로 구분, EMBED_MODEL은 항상 포함)
+ALLOWED_MODELS = {DEFAULT_MODEL} | {
+    name.strip() for name in os.getenv("EMBED_ALLOWED_MODELS", "").split(",") if name.strip()
+}
+
+# Lazy 로딩용 전역 변수 (model 이름 -> 로딩된 model)
+models: Dict[str, SentenceTransformer] = {}
 model_lock = Lock()  # 멀티스레드 환경에서 race condition 방지용
 
-def get_model():
-    print("🔵 Checking if model is loaded...")
-    global model
-    if model is None:
+def load_model(name: str) -> SentenceTransformer:
+    print(f"🔵 Checking if model {name} is loaded...")
+    if name not in models:
         with model_lock:
-            if model is None:
+            if name not in models:
                 try:
-                    print("🔵 Loading SentenceTransformer model...")
-                    model = SentenceTransformer('distiluse-base-multilingual-cased-v2')
+                    print(f"🔵 Loading SentenceTransformer model {name}...")
+                    models[name] = SentenceTransformer(name)
                     print("🟢 Model loaded successfully.")
                 except Exception as e:
                     print(f"Error loading model: {e}")
                     raise HTTPException(status_code=500, detail="Model loading failed")
-    return model
+    return models[name]
+
+async def get_model(name: Optional[str] = None) -> SentenceTransformer:
+    name = name or DEFAULT_MODEL
+    if name not in ALLOWED_MODELS:
+        raise HTTPException(status_code=400, detail=f"Model {name} is not allowed")
+    if name in models:
+        return models[name]
+    # 다운로드와 로딩은 오래 걸리므로 event loop 밖에서
+    return await run_in_threadpool(load_model, name)
 
-# 요청 모델
+# 요청 모델 (model을 생략하면 EMBED_MODEL 사용)
+# /embed는 text 하나 또는 texts 목록을 받음
 class TextRequest(BaseModel):
-    text: str
+    text: Optional[str] = None
+    texts: Optional[List[str]] = None
+    model: Optional[str] = None
 
 class QueryRequest(BaseModel):
     query: str
+    model: Optional[str] = None
 
 # 응답 모델
 class EmbeddingResponse(BaseModel):
     embedding: List[float]
+    model: str
+
+class BatchEmbeddingResponse(BaseModel):
+    embeddings: List[List[float]]
+    model: str
 
 # POST /embed 엔드포인트
-@app.post("/embed", response_model=EmbeddingResponse, dependencies=[Depends(get_api_key)])
-async def embed_text(request: TextRequest, model: SentenceTransformer = Depends(get_model)):
+@app.post("/embed", response_model=Union[EmbeddingResponse, BatchEmbeddingResponse], dependencies=[Depends(get_api_key)])
+async def embed_text(request: TextRequest):
+    if (request.text is None) == (request.texts is None):
+        raise HTTPException(status_code=422, detail="Send exactly one of text or texts")
+    model = await get_model(request.model)
+    name = request.model or DEFAULT_MODEL
     try:
+        if request.texts is not None:
+            embeddings = model.encode(request.texts).tolist() if request.texts else []
+            return BatchEmbeddingResponse(embeddings=embeddings, model=name)
         embedding = model.encode(request.text).tolist()
-        return EmbeddingResponse(embedding=embedding)
+        return EmbeddingResponse(embedding=embedding, model=name)
     except Exception as e:
         print(f"Error generating embedding: {e}")
         raise HTTPException(status_code=500, detail=str(e))
 
 @app.post("/query-embedding", response_model=EmbeddingResponse, dependencies=[Depends(get_api_key)])
-async def query_embedding(request: QueryRequest, model: SentenceTransformer = Depends(get_model)):
+async def query_embedding(request: QueryRequest):
     print(f"Received search query in FastAPI: {request.query}")
+    model = await get_model(request.model)
     try:
         embedding = model.encode(request.query).tolist()
-        return EmbeddingResponse(embedding=embedding)
+        return EmbeddingResponse(embedding=embedding, model=request.model or DEFAULT_MODEL)
     except Exception as e:
         print(f"Error generating embedding: {e}")
         raise HTTPException(status_code=500, detail=str(e))